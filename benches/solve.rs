@@ -0,0 +1,91 @@
+use clap::ValueEnum;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use sudoku_wfc::{
+    board::{parse_collection, Board},
+    wfc::{Budget, CellChoice, Search, ValueChoice},
+};
+
+/// Keeps a bad combination from stalling the whole suite
+const MAX_STEPS: u64 = 1_000_000;
+
+/// The bundled puzzle sets, by name
+fn sets() -> Vec<(&'static str, Vec<Board>)> {
+    let read = |path: &str| {
+        let data = std::fs::read_to_string(path).unwrap();
+        parse_collection(&data).unwrap()
+    };
+
+    let mut examples = read("example/1.txt");
+    examples.extend(read("example/2.txt"));
+    vec![("examples", examples), ("hard", read("example/hard.txt"))]
+}
+
+fn search(cells: CellChoice, values: ValueChoice) -> Search {
+    Search {
+        cells: Some(cells),
+        values,
+        budget: Budget {
+            max_steps: Some(MAX_STEPS),
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+}
+
+/// Name of an option as given on the command line
+fn name(v: impl ValueEnum) -> String {
+    v.to_possible_value().unwrap().get_name().to_string()
+}
+
+fn bench_search(c: &mut Criterion, group: &str, searches: &[(String, Search)]) {
+    let mut group = c.benchmark_group(group);
+    group.sample_size(10);
+    for (set, puzzles) in sets() {
+        for (name, search) in searches {
+            let mut wfc = search.wfc();
+            group.bench_with_input(BenchmarkId::new(name, set), &puzzles, |b, puzzles| {
+                b.iter(|| {
+                    for p in puzzles {
+                        wfc.solve(*p);
+                    }
+                })
+            });
+        }
+    }
+    group.finish();
+}
+
+fn backends(c: &mut Criterion) {
+    let backjump = search(CellChoice::Mrv, ValueChoice::Ascending);
+    let chronological = Search {
+        chronological: true,
+        ..backjump
+    };
+    bench_search(
+        c,
+        "backend",
+        &[
+            ("backjump".to_string(), backjump),
+            ("chronological".to_string(), chronological),
+        ],
+    );
+}
+
+fn cells(c: &mut Criterion) {
+    let searches = CellChoice::value_variants()
+        .iter()
+        .map(|&cells| (name(cells), search(cells, ValueChoice::Ascending)))
+        .collect::<Vec<_>>();
+    bench_search(c, "cells", &searches);
+}
+
+fn values(c: &mut Criterion) {
+    let searches = ValueChoice::value_variants()
+        .iter()
+        .map(|&values| (name(values), search(CellChoice::Mrv, values)))
+        .collect::<Vec<_>>();
+    bench_search(c, "values", &searches);
+}
+
+criterion_group!(benches, backends, cells, values);
+criterion_main!(benches);
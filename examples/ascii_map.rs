@@ -0,0 +1,40 @@
+//! Generates a random ASCII map with the tiled model: each terrain may only
+//! border itself and the terrains one step up or down from it.
+//!
+//!     cargo run --example ascii_map [WIDTH] [HEIGHT]
+
+use sudoku_wfc::wfc::{CellChoice, Search, Tiled, ValueChoice};
+
+/// From lowest to highest
+const TERRAIN: [char; 6] = ['~', '-', '.', ',', 'T', '^'];
+
+fn main() {
+    let mut args = std::env::args().skip(1).map(|a| a.parse().expect("size"));
+    let width = args.next().unwrap_or(60);
+    let height = args.next().unwrap_or(20);
+
+    let mut model = Tiled::new(width, height, TERRAIN.len());
+    for t in 0..TERRAIN.len() as u8 {
+        model.allow_all(t, t);
+        if t > 0 {
+            model.allow_all(t, t - 1);
+        }
+    }
+
+    let search = Search {
+        cells: Some(CellChoice::MrvRandom),
+        values: ValueChoice::Random,
+        ..Default::default()
+    };
+    let wave = model.wave();
+    let mut wfc = search.wfc_for(model);
+    let map = wfc.run(wave).expect("terrain always fits together");
+
+    for row in map.tiles().chunks(width) {
+        let line = row
+            .iter()
+            .map(|t| TERRAIN[t.unwrap() as usize])
+            .collect::<String>();
+        println!("{}", line);
+    }
+}
@@ -1,14 +1,24 @@
-use crate::{board::Board, ui::Ui, wfc::Wfc};
-use crossterm::event::{KeyCode, KeyModifiers};
+use crate::{
+    board::{format::Format, Board},
+    config::{Action, Config, Keybinds},
+    export,
+    ui::{Severity, Theme, ThemeName, Ui},
+    wfc::{Search, Wfc},
+};
 use state::{input::InputState, State};
 use std::{
     io,
+    path::{Path, PathBuf},
     sync::mpsc::{self, channel, Sender},
     time::Duration,
 };
 
+pub mod headless;
 mod state;
 
+/// Time between solver steps while solving
+pub const TICK_RATE: Duration = Duration::from_millis(50);
+
 enum Event {
     Term(crossterm::event::Event),
     Tick,
@@ -22,16 +32,24 @@ struct AppData {
     board: Board,
     ui: Ui,
     wfc: Wfc,
+    keys: Keybinds,
+    // file the board was loaded from
+    path: Option<String>,
+    // layout pencil marks are exported in
+    marks: Format,
 
     tickctl_tx: Sender<TickCtl>,
 }
 
 impl AppData {
-    fn new() -> Self {
+    fn new(board: Board, ui: Ui, keys: Keybinds, wfc: Wfc) -> Self {
         let mut s = Self {
-            board: Board::default(),
-            ui: Ui::new().unwrap(),
-            wfc: Wfc::default(),
+            board,
+            ui,
+            wfc,
+            keys,
+            path: None,
+            marks: Format::Marks,
 
             tickctl_tx: channel().0,
         };
@@ -40,39 +58,55 @@ impl AppData {
         s
     }
 
-    fn from_data(data: String) -> Self {
-        let mut s = Self {
-            board: data.parse().unwrap(),
-            ui: Ui::new().unwrap(),
-            wfc: Wfc::default(),
+    fn toggle_help_ui(&mut self) {
+        if self.ui.has_help() {
+            self.ui.set_help(None);
+        } else {
+            self.ui.set_help(Some(self.keys.help()));
+        }
+    }
 
-            tickctl_tx: channel().0,
+    /// Writes the board next to the file it was loaded from, or to
+    /// `sudoku.svg` in the working directory
+    fn export_svg(&mut self) {
+        let path = match &self.path {
+            Some(p) => Path::new(p).with_extension("svg"),
+            None => PathBuf::from("sudoku.svg"),
         };
 
-        s.toggle_help_ui();
-        s
+        let svg = export::svg::render(&self.board, export::svg::CELL_SIZE);
+        match std::fs::write(&path, svg) {
+            Ok(()) => self
+                .ui
+                .notify(Severity::Success, format!("Exported {}", path.display())),
+            Err(e) => self.ui.notify(
+                Severity::Error,
+                format!("Couldn't write {}: {}", path.display(), e),
+            ),
+        }
     }
 
-    fn toggle_help_ui(&mut self) {
-        let help = || {
-            print!(
-                "Keybinds:\r\n  \
-            ?         -> toggle this message\r\n  \
-            arrows    -> move around the board\r\n  \
-            tab       -> go to next space\r\n  \
-            1..9      -> set current space\r\n  \
-            backspace -> clear current space\r\n  \
-            s         -> start solving\r\n  \
-            c         -> clear solved spaces\r\n  \
-            C         -> clear entire board\r\n  \
-            q or esc  -> quit\r\n"
-            )
+    /// Writes the board with the candidates of its empty spaces, in the
+    /// layout it was loaded in if that has them
+    fn export_marks(&mut self) {
+        let ext = match self.marks {
+            Format::Sdx => "marks.sdx",
+            Format::Json => "marks.json",
+            _ => "marks.txt",
+        };
+        let path = match &self.path {
+            Some(p) => Path::new(p).with_extension(ext),
+            None => PathBuf::from("sudoku").with_extension(ext),
         };
 
-        if self.ui.has((0, 40)) {
-            self.ui.remove_msg((0, 40));
-        } else {
-            self.ui.add_msg((0, 40), help);
+        match std::fs::write(&path, self.marks.write(&self.board)) {
+            Ok(()) => self
+                .ui
+                .notify(Severity::Success, format!("Exported {}", path.display())),
+            Err(e) => self.ui.notify(
+                Severity::Error,
+                format!("Couldn't write {}: {}", path.display(), e),
+            ),
         }
     }
 }
@@ -85,22 +119,54 @@ pub struct App {
 }
 
 impl App {
-    pub fn new() -> Self {
-        Self {
-            data: AppData::new(),
-            state: Box::new(InputState::default()),
+    pub fn new(config: &Config, search: Search) -> Self {
+        Self::with_ui(Board::default(), Ui::new().unwrap(), config, search)
+    }
 
-            exit: false,
+    /// Starts with the board from `path`, or an empty one and an error
+    /// message if it can't be loaded
+    pub fn from_file(path: &str, config: &Config, search: Search) -> Self {
+        match load(path) {
+            Ok((board, format)) => {
+                let mut app = Self::with_ui(board, Ui::new().unwrap(), config, search);
+                app.data.path = Some(path.to_string());
+                if format.has_candidates() {
+                    app.data.marks = format;
+                }
+                app.data
+                    .ui
+                    .notify(Severity::Info, format!("Loaded {}", path));
+                app
+            }
+            Err(e) => {
+                let mut app = Self::new(config, search);
+                app.data.ui.notify_for(Severity::Error, e, None);
+                app
+            }
         }
     }
 
-    pub fn from_data(data: String) -> Self {
-        Self {
-            data: AppData::from_data(data),
+    fn with_ui(board: Board, mut ui: Ui, config: &Config, search: Search) -> Self {
+        let theme = config.theme.unwrap_or_else(ThemeName::from_env);
+        ui.set_theme(Theme::named(theme));
+
+        let (keys, err) = match Keybinds::new(&config.keys) {
+            Ok(keys) => (keys, None),
+            Err(e) => (Keybinds::default(), Some(e)),
+        };
+
+        let mut app = Self {
+            data: AppData::new(board, ui, keys, search.wfc()),
             state: Box::new(InputState::default()),
 
             exit: false,
+        };
+        if let Some(e) = err {
+            app.data
+                .ui
+                .notify_for(Severity::Error, format!("Bad keybinds: {}", e), None);
         }
+        app
     }
 
     pub fn run(&mut self) -> io::Result<()> {
@@ -113,26 +179,21 @@ impl App {
         let _t = std::thread::spawn(move || ticker(tickctl_rx, event_tx));
         self.data.tickctl_tx = tickctl_tx;
 
-        self.data.ui.draw(&self.data.board)?;
-        self.state.draw(&mut self.data);
+        self.draw()?;
 
-        while let Ok(e) = event_rx.recv() {
-            match e {
-                Event::Term(e) => self.handle_term_event(e),
-                Event::Tick => {
-                    let new_state = self.state.handle_tick_event(&mut self.data);
-                    if let Some(state) = new_state {
-                        self.state = state
-                    }
-                }
-            };
+        loop {
+            // wake up now and then so timed out messages go away
+            match event_rx.recv_timeout(Duration::from_millis(250)) {
+                Ok(e) => self.handle_event(e),
+                Err(mpsc::RecvTimeoutError::Timeout) => (),
+                Err(mpsc::RecvTimeoutError::Disconnected) => break,
+            }
 
             if self.exit {
                 break;
             }
 
-            self.data.ui.draw(&self.data.board)?;
-            self.state.draw(&mut self.data);
+            self.draw()?;
         }
 
         Ok(())
@@ -140,43 +201,96 @@ impl App {
 }
 
 impl App {
-    fn handle_term_event(&mut self, e: crossterm::event::Event) {
+    fn handle_event(&mut self, e: Event) {
         match e {
-            crossterm::event::Event::Key(k) => match k.code {
-                // Quit
-                KeyCode::Char('q') | KeyCode::Esc => self.exit = true,
-                KeyCode::Char('c') if k.modifiers.contains(KeyModifiers::CONTROL) => {
-                    self.exit = true
-                }
-                KeyCode::Char('d') if k.modifiers.contains(KeyModifiers::CONTROL) => {
-                    self.exit = true
+            Event::Term(e) => self.handle_term_event(e),
+            Event::Tick => {
+                let new_state = self.state.handle_tick_event(&mut self.data);
+                if let Some(state) = new_state {
+                    self.state = state
                 }
+            }
+        };
+    }
 
-                // Input
-                KeyCode::Char('c') => {
-                    self.state = Box::new(InputState::default());
-                    self.data.board.clear_maybe();
-                }
-                KeyCode::Char('C') => {
-                    self.state = Box::new(InputState::default());
-                    self.data.board.clear_all();
-                }
-                KeyCode::Char('?') => {
-                    self.data.toggle_help_ui();
-                }
+    fn draw(&mut self) -> io::Result<()> {
+        // the state only records what it wants, such as the cursor, for
+        // the ui to draw in one go
+        self.state.draw(&mut self.data);
+        self.data.ui.draw(&self.data.board, self.state.selected())
+    }
 
-                _ => {
-                    let new_state = self.state.handle_key_event(&mut self.data, k);
-                    if let Some(state) = new_state {
-                        self.state = state;
+    fn handle_term_event(&mut self, e: crossterm::event::Event) {
+        match e {
+            crossterm::event::Event::Key(k) => {
+                let new_state = match self.data.keys.action(&k) {
+                    Some(Action::Quit) => {
+                        self.exit = true;
+                        None
+                    }
+                    Some(Action::ClearSolved) => {
+                        self.data.board.clear_maybe();
+                        Some(Box::new(InputState::default()) as Box<dyn State>)
+                    }
+                    Some(Action::ClearAll) => {
+                        self.data.board.clear_all();
+                        Some(Box::new(InputState::default()) as Box<dyn State>)
                     }
+                    Some(Action::Help) => {
+                        self.data.toggle_help_ui();
+                        None
+                    }
+                    Some(Action::UnderlineConflicts) => {
+                        self.data.ui.toggle_underline_conflicts();
+                        None
+                    }
+                    Some(Action::ExportSvg) => {
+                        self.data.export_svg();
+                        None
+                    }
+                    Some(Action::ExportMarks) => {
+                        self.data.export_marks();
+                        None
+                    }
+                    Some(a) => self.state.handle_action(&mut self.data, a),
+                    None => self.state.handle_key_event(&mut self.data, k),
+                };
+                if let Some(state) = new_state {
+                    self.state = state;
                 }
-            },
+            }
+            crossterm::event::Event::Resize(width, height) => self.data.ui.resize(width, height),
+            crossterm::event::Event::Mouse(m) => {
+                let new_state = self.state.handle_mouse_event(&mut self.data, m);
+                if let Some(state) = new_state {
+                    self.state = state;
+                }
+            }
             _ => (),
         }
     }
 }
 
+pub fn load_board(path: &str) -> Result<Board, String> {
+    load(path).map(|(b, _)| b)
+}
+
+/// The first puzzle of a file in any format, pencil marks included, and
+/// the format it was in
+fn load(path: &str) -> Result<(Board, Format), String> {
+    let data =
+        std::fs::read_to_string(path).map_err(|e| format!("Couldn't read {}: {}", path, e))?;
+    let format = Format::from_path(path).unwrap_or_else(|| Format::detect(&data));
+    let err = |e| format!("Couldn't load {}: {}", path, e);
+    // a file of one grid is read as a whole, empty rows and all
+    if format == Format::Grid && data.lines().count() <= 9 {
+        return data.parse().map(|b| (b, format)).map_err(err);
+    }
+    let boards = format.parse(&data).map_err(err)?;
+    // an empty file is an empty board
+    Ok((boards.into_iter().next().unwrap_or_default(), format))
+}
+
 fn crossterm_el(event_tx: mpsc::Sender<Event>) -> io::Result<()> {
     loop {
         let e = crossterm::event::read()?;
@@ -190,7 +304,7 @@ fn ticker(ctl: mpsc::Receiver<TickCtl>, tick: mpsc::Sender<Event>) {
             Ok(TickCtl::Start) => loop {
                 tick.send(Event::Tick).unwrap();
 
-                std::thread::sleep(Duration::from_millis(50));
+                std::thread::sleep(TICK_RATE);
                 if matches!(
                     ctl.try_recv(),
                     Ok(TickCtl::Stop) | Err(mpsc::TryRecvError::Disconnected)
@@ -0,0 +1,139 @@
+use super::*;
+use crate::ui::{Frame, Time};
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyEventState, KeyModifiers};
+use std::{cell::Cell, rc::Rc, sync::mpsc::Receiver};
+
+/// Upper bound on ticks delivered for a single `<wait>`
+const MAX_WAIT_TICKS: usize = 1_000_000;
+/// How far a key press moves the clock, so recordings show each one
+const KEY_DELAY: Duration = Duration::from_millis(300);
+
+/// Time as seen by a headless session: it moves by `TICK_RATE` per tick and
+/// `KEY_DELAY` per key press instead of with the wall clock
+pub type Clock = Rc<Cell<Duration>>;
+
+/// Drives an `App` without a terminal. Events are fed in by the caller
+/// instead of read from crossterm, and ticks are delivered on demand
+/// while the solver would have the ticker running.
+pub struct Headless {
+    app: App,
+    tickctl_rx: Receiver<TickCtl>,
+    ticking: bool,
+    clock: Clock,
+}
+
+impl Headless {
+    /// Session whose screen updates are written to `out`
+    pub fn new(
+        board: Board,
+        (width, height): (u16, u16),
+        config: &Config,
+        search: Search,
+        out: Box<dyn io::Write>,
+        clock: Clock,
+    ) -> io::Result<Self> {
+        let mut ui = Ui::with_writer(out, width, height);
+        ui.set_time(Time::Session(clock.clone()));
+        let mut app = App::with_ui(board, ui, config, search);
+
+        let (tickctl_tx, tickctl_rx) = mpsc::channel();
+        app.data.tickctl_tx = tickctl_tx;
+        app.draw()?;
+
+        Ok(Self {
+            app,
+            tickctl_rx,
+            ticking: false,
+            clock,
+        })
+    }
+
+    pub fn send(&mut self, e: crossterm::event::Event) -> io::Result<()> {
+        self.app.handle_event(Event::Term(e));
+        self.update_ticker();
+        self.app.draw()
+    }
+
+    pub fn key(&mut self, code: KeyCode) -> io::Result<()> {
+        let k = KeyEvent {
+            code,
+            modifiers: KeyModifiers::NONE,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        };
+        self.clock.set(self.clock.get() + KEY_DELAY);
+        self.send(crossterm::event::Event::Key(k))
+    }
+
+    /// Delivers one tick if the ticker is running, returning whether it was
+    pub fn tick(&mut self) -> io::Result<bool> {
+        if !self.ticking {
+            return Ok(false);
+        }
+
+        self.clock.set(self.clock.get() + TICK_RATE);
+        self.app.handle_event(Event::Tick);
+        self.update_ticker();
+        self.app.draw()?;
+        Ok(true)
+    }
+
+    /// Ticks until the ticker stops, e.g. when solving finishes
+    pub fn wait(&mut self) -> io::Result<()> {
+        for _ in 0..MAX_WAIT_TICKS {
+            if !self.tick()? {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs a script of key presses. Characters are sent as they are,
+    /// except for `<name>` tokens: `<up>`, `<down>`, `<left>`, `<right>`,
+    /// `<tab>`, `<bs>`, `<esc>`, `<enter>`, `<lt>` for a literal `<` and
+    /// `<wait>` which ticks until the solver is done.
+    pub fn run_script(&mut self, script: &str) -> Result<(), String> {
+        let mut chars = script.chars();
+        while let Some(c) = chars.next() {
+            if self.app.exit {
+                break;
+            }
+
+            let res = if c == '<' {
+                let name = chars.by_ref().take_while(|&c| c != '>').collect::<String>();
+                let code = match name.as_str() {
+                    "up" => KeyCode::Up,
+                    "down" => KeyCode::Down,
+                    "left" => KeyCode::Left,
+                    "right" => KeyCode::Right,
+                    "tab" => KeyCode::Tab,
+                    "bs" => KeyCode::Backspace,
+                    "esc" => KeyCode::Esc,
+                    "enter" => KeyCode::Enter,
+                    "lt" => KeyCode::Char('<'),
+                    "wait" => {
+                        self.wait().map_err(|e| e.to_string())?;
+                        continue;
+                    }
+                    _ => return Err(format!("Unknown key <{}>", name)),
+                };
+                self.key(code)
+            } else {
+                self.key(KeyCode::Char(c))
+            };
+            res.map_err(|e| e.to_string())?;
+        }
+
+        Ok(())
+    }
+
+    pub fn screen(&self) -> &Frame {
+        self.app.data.ui.screen()
+    }
+
+    fn update_ticker(&mut self) {
+        while let Ok(ctl) = self.tickctl_rx.try_recv() {
+            self.ticking = matches!(ctl, TickCtl::Start);
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use crossterm::event::{MouseButton, MouseEventKind};
 use solve::SolveState;
 
 use super::*;
@@ -9,59 +10,50 @@ pub struct InputState {
 
 impl InputState {
     fn set_cursor(&self, ui: &mut Ui) {
-        ui.set_cursor_onboard(Some(self.selected)).unwrap();
+        ui.set_cursor_onboard(Some(self.selected));
     }
 }
 
 impl State for InputState {
-    fn handle_key_event(&mut self, data: &mut AppData, k: KeyEvent) -> Option<Box<dyn State>> {
-        match k.code {
-            KeyCode::Up | KeyCode::Char('k') => {
-                self.selected.0 -= 1;
-                if self.selected.0 == usize::MAX {
-                    self.selected.0 = 8;
-                }
-            }
-            KeyCode::Down | KeyCode::Char('j') => {
+    fn handle_action(&mut self, data: &mut AppData, a: Action) -> Option<Box<dyn State>> {
+        match a {
+            Action::Up => self.selected.0 = (self.selected.0 + 8) % 9,
+            Action::Down => {
                 self.selected.0 += 1;
                 if self.selected.0 == 9 {
                     self.selected.0 = 0;
                 }
             }
-            KeyCode::Left | KeyCode::Char('h') => {
-                self.selected.1 -= 1;
-                if self.selected.1 == usize::MAX {
-                    self.selected.1 = 8;
-                }
-            }
-            KeyCode::Right | KeyCode::Char('l') => {
+            Action::Left => self.selected.1 = (self.selected.1 + 8) % 9,
+            Action::Right => {
                 self.selected.1 += 1;
                 if self.selected.1 == 9 {
                     self.selected.1 = 0;
                 }
             }
-            KeyCode::Tab => {
-                self.selected.1 = self.selected.1 + 1;
+            Action::Next => {
+                self.selected.1 += 1;
                 if self.selected.1 == 9 {
                     self.selected.1 = 0;
-                    self.selected.0 = self.selected.0 + 1;
+                    self.selected.0 += 1;
                     if self.selected.0 == 9 {
                         self.selected.0 = 0;
                     }
                 }
             }
 
-            KeyCode::Char(c) if matches!(c, '1'..='9') => {
-                let digit = c.to_digit(10).unwrap() as u8;
-                data.board.set_cell(self.selected, Some(digit));
-            }
-            KeyCode::Backspace | KeyCode::Delete => data.board.set_cell(self.selected, None),
+            Action::Erase => data.board.set_cell(self.selected, None),
 
-            KeyCode::Char('s') => {
+            Action::Solve => {
                 if !data.board.can_solve() {
-                    data.ui.add_msg((0, 38), || {
-                        print!("Can't start solving. Board is invalid\r\n")
-                    });
+                    data.ui.notify(
+                        Severity::Warning,
+                        format!(
+                            "Can't start solving. Board has {} conflicting and {} dead spaces",
+                            data.board.conflicts(),
+                            data.board.dead_cells(),
+                        ),
+                    );
                 } else {
                     return Some(Box::new(SolveState::new(data)));
                 }
@@ -73,7 +65,34 @@ impl State for InputState {
         None
     }
 
+    fn handle_key_event(&mut self, data: &mut AppData, k: KeyEvent) -> Option<Box<dyn State>> {
+        if let KeyCode::Char(c @ '1'..='9') = k.code {
+            let digit = c.to_digit(10).unwrap() as u8;
+            data.board.set_cell(self.selected, Some(digit));
+        }
+
+        None
+    }
+
+    fn handle_mouse_event(&mut self, data: &mut AppData, m: MouseEvent) -> Option<Box<dyn State>> {
+        let MouseEventKind::Down(button) = m.kind else {
+            return None;
+        };
+        let (pos, num) = data.ui.cell_at(m.column, m.row)?;
+
+        self.selected = pos;
+        if let (MouseButton::Left | MouseButton::Right, Some(n)) = (button, num) {
+            data.board.toggle_note(pos, n);
+        }
+
+        None
+    }
+
     fn draw(&self, data: &mut AppData) {
         self.set_cursor(&mut data.ui);
     }
+
+    fn selected(&self) -> Option<(usize, usize)> {
+        Some(self.selected)
+    }
 }
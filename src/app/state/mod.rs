@@ -1,5 +1,5 @@
 use super::*;
-use crossterm::event::KeyEvent;
+use crossterm::event::{KeyCode, KeyEvent, MouseEvent};
 
 pub mod input;
 pub mod solve;
@@ -10,6 +10,16 @@ pub trait State {
         None
     }
 
+    #[allow(unused)]
+    fn handle_action(&mut self, data: &mut AppData, a: Action) -> Option<Box<dyn State>> {
+        None
+    }
+
+    #[allow(unused)]
+    fn handle_mouse_event(&mut self, data: &mut AppData, m: MouseEvent) -> Option<Box<dyn State>> {
+        None
+    }
+
     #[allow(unused)]
     fn handle_tick_event(&mut self, data: &mut AppData) -> Option<Box<dyn State>> {
         None
@@ -17,4 +27,9 @@ pub trait State {
 
     #[allow(unused)]
     fn draw(&self, data: &mut AppData) {}
+
+    /// Cell under the cursor, used to highlight its peers
+    fn selected(&self) -> Option<(usize, usize)> {
+        None
+    }
 }
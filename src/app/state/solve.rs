@@ -4,6 +4,8 @@ use super::*;
 
 pub struct SolveState {
     tickctl_tx: Sender<TickCtl>,
+    // session time solving started at
+    started: Duration,
 }
 
 impl SolveState {
@@ -14,6 +16,7 @@ impl SolveState {
 
         Self {
             tickctl_tx: data.tickctl_tx.clone(),
+            started: data.ui.now(),
         }
     }
 }
@@ -27,15 +30,30 @@ impl Drop for SolveState {
 impl State for SolveState {
     fn handle_tick_event(&mut self, data: &mut AppData) -> Option<Box<dyn State>> {
         let res = data.wfc.step();
+        let elapsed = data.ui.now() - self.started;
         match res {
             StepResult::Complete(b) => {
                 data.board = b;
-                data.ui.add_msg((0, 38), || print!("Solved!"));
+                data.ui.notify(
+                    Severity::Success,
+                    format!("Solved in {:.1?} after {} steps", elapsed, data.wfc.steps()),
+                );
                 return Some(Box::new(InputState::default()));
             }
             StepResult::InProgress(b) => data.board = b,
             StepResult::Impossible => {
-                data.ui.add_msg((0, 38), || print!("No solution!"));
+                data.ui.notify(
+                    Severity::Error,
+                    format!("No solution! Searched for {} steps", data.wfc.steps()),
+                );
+                data.board.clear_maybe();
+                return Some(Box::new(InputState::default()));
+            }
+            StepResult::GaveUp => {
+                data.ui.notify(
+                    Severity::Warning,
+                    format!("Gave up after {} steps", data.wfc.steps()),
+                );
                 data.board.clear_maybe();
                 return Some(Box::new(InputState::default()));
             }
@@ -0,0 +1,99 @@
+use crate::{board::Board, wfc::Search};
+use std::{
+    collections::BTreeMap,
+    sync::{mpsc, Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Puzzles queued or waiting to be handed back, per thread. Bounds memory
+/// when one slow puzzle holds up the ones after it
+const WINDOW_PER_JOB: usize = 64;
+
+/// How solving one puzzle went
+pub struct Solved {
+    pub puzzle: Board,
+    pub solution: Option<Board>,
+    pub time: Duration,
+    pub steps: u64,
+    pub backtracks: u64,
+    pub gave_up: bool,
+}
+
+/// One thread per core, or `requested` if given
+pub fn jobs(requested: Option<usize>) -> usize {
+    requested
+        .or_else(|| thread::available_parallelism().ok().map(|n| n.get()))
+        .unwrap_or(1)
+        .max(1)
+}
+
+/// Solves `puzzles` on `jobs` threads, each with its own solver, and calls
+/// `each` with the results in input order. Puzzles are read as the threads
+/// need them, so memory stays flat however many there are
+pub fn solve(
+    puzzles: impl Iterator<Item = Board>,
+    search: &Search,
+    jobs: usize,
+    mut each: impl FnMut(Solved),
+) {
+    let jobs = jobs.max(1);
+    let (work_tx, work_rx) = mpsc::channel::<(usize, Board)>();
+    let work_rx = Arc::new(Mutex::new(work_rx));
+    let (done_tx, done_rx) = mpsc::channel();
+
+    thread::scope(|s| {
+        for _ in 0..jobs {
+            let work_rx = work_rx.clone();
+            let done_tx = done_tx.clone();
+            let mut wfc = search.wfc();
+            s.spawn(move || loop {
+                // the lock is only held while waiting for the next puzzle
+                let job = work_rx.lock().unwrap().recv();
+                let (i, puzzle) = match job {
+                    Ok(job) => job,
+                    Err(_) => break,
+                };
+
+                let start = Instant::now();
+                let solution = wfc.solve(puzzle);
+                let solved = Solved {
+                    puzzle,
+                    solution,
+                    time: start.elapsed(),
+                    steps: wfc.steps(),
+                    backtracks: wfc.backtracks(),
+                    gave_up: wfc.gave_up(),
+                };
+                if done_tx.send((i, solved)).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(done_tx);
+
+        let mut puzzles = puzzles.fuse();
+        let (mut sent, mut next) = (0, 0);
+        let mut pending = BTreeMap::new();
+        loop {
+            while sent - next < jobs * WINDOW_PER_JOB {
+                match puzzles.next() {
+                    Some(p) => work_tx.send((sent, p)).unwrap(),
+                    None => break,
+                }
+                sent += 1;
+            }
+            if next == sent {
+                break;
+            }
+
+            let (i, solved) = done_rx.recv().unwrap();
+            pending.insert(i, solved);
+            while let Some(solved) = pending.remove(&next) {
+                each(solved);
+                next += 1;
+            }
+        }
+        drop(work_tx);
+    });
+}
@@ -0,0 +1,242 @@
+use crate::{
+    batch::{self, Solved},
+    board::Board,
+    wfc::Search,
+};
+use std::{
+    fmt::Display,
+    time::{Duration, Instant},
+};
+
+/// How solving one puzzle went
+struct Run {
+    /// Position in the file, from 1
+    puzzle: usize,
+    time: Duration,
+    steps: u64,
+    backtracks: u64,
+    outcome: Outcome,
+}
+
+#[derive(PartialEq, Eq, Clone, Copy)]
+enum Outcome {
+    Solved,
+    NoSolution,
+    GaveUp,
+}
+
+/// Bits of a value kept below its highest one in the histogram, so the
+/// median is at most 1/16 below the real one
+const SUB_BITS: u32 = 4;
+
+/// Statistics from solving every puzzle of a collection. Nothing is kept per
+/// puzzle but the slowest ones, so large files don't fill up memory
+#[derive(Default)]
+pub struct Report {
+    puzzles: usize,
+    solved: usize,
+    no_solution: usize,
+    gave_up: usize,
+    /// In nanoseconds
+    times: Stats,
+    steps: Stats,
+    backtracks: Stats,
+    /// Slowest first
+    slowest: Vec<Run>,
+    keep: usize,
+    total: Duration,
+    jobs: usize,
+}
+
+/// Count, sum and max of some values, and a histogram of them on a log
+/// scale for the median, all in a fixed amount of memory
+struct Stats {
+    count: u64,
+    sum: u128,
+    max: u64,
+    buckets: Vec<u64>,
+}
+
+impl Default for Stats {
+    fn default() -> Self {
+        Self {
+            count: 0,
+            sum: 0,
+            max: 0,
+            buckets: vec![0; bucket(u64::MAX) + 1],
+        }
+    }
+}
+
+impl Stats {
+    fn add(&mut self, v: u64) {
+        self.count += 1;
+        self.sum += v as u128;
+        self.max = self.max.max(v);
+        self.buckets[bucket(v)] += 1;
+    }
+
+    /// Mean, median and max, the median rounded down to its bucket
+    fn summary(&self) -> Option<(f64, u64, u64)> {
+        if self.count == 0 {
+            return None;
+        }
+        let mean = self.sum as f64 / self.count as f64;
+
+        let mut below = 0;
+        let median = self.buckets.iter().position(|&n| {
+            below += n;
+            below > self.count / 2
+        })?;
+        Some((mean, smallest(median).min(self.max), self.max))
+    }
+}
+
+/// Histogram bucket of a value: its highest bit and the `SUB_BITS` under it.
+/// Values with no more bits than that get a bucket each
+fn bucket(v: u64) -> usize {
+    if v < 1 << SUB_BITS {
+        return v as usize;
+    }
+    let high = 63 - v.leading_zeros();
+    let sub = (v >> (high - SUB_BITS)) & ((1 << SUB_BITS) - 1);
+    (((high - SUB_BITS + 1) << SUB_BITS) as u64 + sub) as usize
+}
+
+/// The smallest value in bucket `b`
+fn smallest(b: usize) -> u64 {
+    let b = b as u64;
+    if b < 1 << SUB_BITS {
+        return b;
+    }
+    let high = (b >> SUB_BITS) + SUB_BITS as u64 - 1;
+    let sub = b & ((1 << SUB_BITS) - 1);
+    ((1 << SUB_BITS) | sub) << (high - SUB_BITS as u64)
+}
+
+/// Solves the puzzles on `jobs` threads, keeping the `slowest` ones for the
+/// report
+pub fn run(
+    puzzles: impl Iterator<Item = Board>,
+    search: &Search,
+    jobs: usize,
+    slowest: usize,
+) -> Report {
+    let mut report = Report {
+        keep: slowest,
+        jobs,
+        ..Default::default()
+    };
+
+    let started = Instant::now();
+    batch::solve(puzzles, search, jobs, |s| report.add(s));
+    report.total = started.elapsed();
+    report
+}
+
+impl Report {
+    fn add(&mut self, s: Solved) {
+        self.puzzles += 1;
+        let run = Run {
+            puzzle: self.puzzles,
+            time: s.time,
+            steps: s.steps,
+            backtracks: s.backtracks,
+            outcome: match s.solution {
+                Some(_) => Outcome::Solved,
+                None if s.gave_up => Outcome::GaveUp,
+                None => Outcome::NoSolution,
+            },
+        };
+
+        match run.outcome {
+            Outcome::Solved => self.solved += 1,
+            Outcome::NoSolution => self.no_solution += 1,
+            Outcome::GaveUp => self.gave_up += 1,
+        }
+        self.times.add(run.time.as_nanos() as u64);
+        self.steps.add(run.steps);
+        self.backtracks.add(run.backtracks);
+
+        let at = self.slowest.partition_point(|r| r.time >= run.time);
+        if at < self.keep {
+            self.slowest.insert(at, run);
+            self.slowest.truncate(self.keep);
+        }
+    }
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{} puzzles: {} solved, {} without solution, {} gave up",
+            self.puzzles, self.solved, self.no_solution, self.gave_up,
+        )?;
+        writeln!(
+            f,
+            "{:.2?} in total on {} threads, {:.1} puzzles/s",
+            self.total,
+            self.jobs,
+            self.puzzles as f64 / self.total.as_secs_f64(),
+        )?;
+
+        writeln!(f, "\n{:<12}{:>12}{:>12}{:>12}", "", "mean", "median", "max")?;
+        if let Some((mean, median, max)) = self.times.summary() {
+            let time = |v: f64| format!("{:.2?}", Duration::from_secs_f64(v / 1e9));
+            writeln!(
+                f,
+                "{:<12}{:>12}{:>12}{:>12}",
+                "time",
+                time(mean),
+                time(median as f64),
+                time(max as f64)
+            )?;
+        }
+        for (name, values) in [("steps", &self.steps), ("backtracks", &self.backtracks)] {
+            if let Some((mean, median, max)) = values.summary() {
+                writeln!(f, "{:<12}{:>12.1}{:>12}{:>12}", name, mean, median, max)?;
+            }
+        }
+
+        writeln!(f, "\nSlowest:")?;
+        for r in &self.slowest {
+            writeln!(
+                f,
+                "  #{:<6}{:>12.2?}{:>10} steps{:>10} backtracks{}",
+                r.puzzle,
+                r.time,
+                r.steps,
+                r.backtracks,
+                match r.outcome {
+                    Outcome::Solved => "",
+                    Outcome::NoSolution => ", no solution",
+                    Outcome::GaveUp => ", gave up",
+                }
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buckets_start_at_their_smallest() {
+        for b in 0..=bucket(u64::MAX) {
+            assert_eq!(bucket(smallest(b)), b);
+        }
+        assert_eq!(bucket(u64::MAX), Stats::default().buckets.len() - 1);
+    }
+
+    #[test]
+    fn median_is_close() {
+        let mut stats = Stats::default();
+        (1..=1000).for_each(|v| stats.add(v));
+        let (mean, median, max) = stats.summary().unwrap();
+        assert_eq!((mean, max), (500.5, 1000));
+        assert!((501 - 501 / 16..=501).contains(&median), "{}", median);
+    }
+}
@@ -1,10 +1,12 @@
-use crossterm::style::Stylize;
 use std::{
     fmt::Display,
+    io::{self, BufRead},
     ops::{Index, IndexMut},
     str::FromStr,
 };
 
+pub mod format;
+
 #[derive(Default, PartialEq, Eq, Clone, Copy)]
 pub enum Cell {
     #[default]
@@ -21,10 +23,6 @@ impl Cell {
         matches!(self, Cell::Maybe(_))
     }
 
-    pub fn is_empty(&self) -> bool {
-        matches!(self, Cell::Empty)
-    }
-
     pub fn maybe_get_num(&self, num: u8) -> bool {
         if let Cell::Maybe(m) = self {
             (m & (1 << num as u16)) != 0
@@ -49,15 +47,14 @@ impl Cell {
 
     pub fn maybe_unset(&mut self, value: u8) {
         let mask = !(1 << value);
-        match self {
-            Cell::Maybe(m) => *m = *m & mask,
-            _ => (),
+        if let Cell::Maybe(m) = self {
+            *m &= mask;
         }
     }
 }
 
 impl Cell {
-    fn value(&self) -> Option<u8> {
+    pub fn value(&self) -> Option<u8> {
         match self {
             Cell::Good(v) => Some(*v),
             Cell::Bad(v) => Some(*v),
@@ -81,12 +78,9 @@ impl Cell {
 
 impl Display for Cell {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Cell::Good(v) => write!(f, "{}", v.to_string().blue().bold()),
-            Cell::Bad(v) => write!(f, "{}", v.to_string().red().bold()),
-            Cell::Collapsed(v) => write!(f, "{}", v.to_string().green().bold()),
-            Cell::Empty => write!(f, " "),
-            Cell::Maybe(_) => unimplemented!(),
+        match self.value() {
+            Some(v) => write!(f, "{}", v),
+            None => write!(f, " "),
         }
     }
 }
@@ -94,51 +88,155 @@ impl Display for Cell {
 #[derive(Clone, Copy)]
 pub struct Board {
     cells: [Cell; 81],
+    // houses that contain a duplicated value, indexed like `house`
+    bad_houses: [bool; HOUSES],
+    // pencil marks the user left on empty cells, apart from the candidates
+    // the solver works with
+    notes: [u16; 81],
 }
 
 impl Default for Board {
     fn default() -> Self {
         Self {
             cells: [Cell::default(); 81],
+            bad_houses: [false; HOUSES],
+            notes: [0; 81],
         }
     }
 }
 
+/// Rows, columns and squares of the board
+pub const HOUSES: usize = 27;
+
+/// Cell indexes of a house. Houses 0..9 are rows, 9..18 columns and 18..27 squares
+pub fn house(h: usize) -> [usize; 9] {
+    let n = h % 9;
+    std::array::from_fn(|i| match h / 9 {
+        0 => n * 9 + i,
+        1 => i * 9 + n,
+        _ => ((n / 3) * 3 + i / 3) * 9 + (n % 3) * 3 + i % 3,
+    })
+}
+
+/// The row, column and square a cell belongs to
+pub fn houses_of(idx: usize) -> [usize; 3] {
+    let (row, col) = (idx / 9, idx % 9);
+    [row, 9 + col, 18 + (row / 3) * 3 + col / 3]
+}
+
+/// The other cells sharing a house with a cell
+pub fn peers(idx: usize) -> Vec<usize> {
+    let mut peers = houses_of(idx)
+        .into_iter()
+        .flat_map(house)
+        .filter(|&i| i != idx)
+        .collect::<Vec<_>>();
+    peers.sort_unstable();
+    peers.dedup();
+    peers
+}
+
+pub fn are_peers(a: usize, b: usize) -> bool {
+    a != b && houses_of(a).iter().any(|h| houses_of(b).contains(h))
+}
+
 impl Board {
     pub fn set_cell(&mut self, idx: (usize, usize), value: Option<u8>) {
         self.cells[idx.0 * 9 + idx.1] = match value {
             Some(v) => Cell::Good(v),
             None => Cell::Empty,
         };
+        self.notes[idx.0 * 9 + idx.1] = 0;
         self.calc_cell_states();
     }
 
+    /// Toggles a pencil mark on an unfilled cell. Marks on an empty cell are
+    /// only notes for the user, while those of a cell with candidates, like
+    /// ones loaded from a pencil mark grid, are the candidates themselves
+    pub fn toggle_note(&mut self, idx: (usize, usize), value: u8) {
+        let i = idx.0 * 9 + idx.1;
+        match self.cells[i] {
+            Cell::Empty => self.notes[i] ^= 1 << value,
+            Cell::Maybe(m) if m == 1 << value => self.cells[i] = Cell::Empty,
+            Cell::Maybe(m) => self.cells[i] = Cell::Maybe(m ^ (1 << value)),
+            _ => (),
+        }
+    }
+
+    /// Bit mask of the notes on an empty cell, values 1-9
+    pub fn notes(&self, idx: usize) -> u16 {
+        match self.cells[idx] {
+            Cell::Empty => self.notes[idx],
+            _ => 0,
+        }
+    }
+
     pub fn can_solve(&self) -> bool {
         for i in 0..81 {
-            if matches!(self.cells[i], Cell::Bad(_)) {
+            if matches!(self.cells[i], Cell::Bad(_)) || self.is_dead(i) {
                 return false;
             }
         }
         true
     }
 
+    pub fn conflicts(&self) -> usize {
+        self.cells
+            .iter()
+            .filter(|c| matches!(c, Cell::Bad(_)))
+            .count()
+    }
+
+    pub fn dead_cells(&self) -> usize {
+        (0..81).filter(|&i| self.is_dead(i)).count()
+    }
+
+    pub fn in_conflicting_house(&self, idx: usize) -> bool {
+        houses_of(idx).into_iter().any(|h| self.bad_houses[h])
+    }
+
+    /// Bit mask of values 1-9 not yet used by any peer of the cell
+    pub fn candidates(&self, idx: usize) -> u16 {
+        let mut mask = 0b1111111110;
+        for h in houses_of(idx) {
+            for i in house(h) {
+                if let Some(v) = self.cells[i].value() {
+                    mask &= !(1 << v);
+                }
+            }
+        }
+        mask
+    }
+
+    /// An unfilled cell that has no value left to take
+    pub fn is_dead(&self, idx: usize) -> bool {
+        match self.cells[idx] {
+            Cell::Empty => self.candidates(idx) == 0,
+            Cell::Maybe(m) => m & self.candidates(idx) == 0,
+            _ => false,
+        }
+    }
+
+    /// Clears what the solver filled in, keeping the user's notes
     pub fn clear_maybe(&mut self) {
         for i in 0..81 {
             if matches!(self.cells[i], Cell::Maybe(_) | Cell::Collapsed(_)) {
                 self.cells[i] = Cell::Empty;
             }
         }
+        self.calc_cell_states();
     }
 
     pub fn clear_all(&mut self) {
         for i in 0..81 {
             self.cells[i] = Cell::Empty;
         }
+        self.notes = [0; 81];
+        self.calc_cell_states();
     }
 
     pub fn get_uncollapsed(&self) -> Vec<(usize, Cell)> {
         self.cells
-            .clone()
             .into_iter()
             .enumerate()
             .filter(|(_, c)| c.is_maybe())
@@ -164,31 +262,13 @@ impl Board {
 
     pub fn init_maybe(&mut self) {
         for i in 0..81 {
-            if !self.cells[i].is_empty() {
-                continue;
-            }
-
-            let mut value = Cell::Maybe(0b1111111110);
-
-            let cell = (i / 9, i % 9);
-            let square = ((cell.0 / 3) * 3, (cell.1 / 3) * 3);
-            self.row(cell.0).for_each(|c| {
-                if let Some(v) = c.value() {
-                    value.maybe_unset(v)
-                }
-            });
-            self.col(cell.1).for_each(|c| {
-                if let Some(v) = c.value() {
-                    value.maybe_unset(v)
-                }
-            });
-            self.square(square).for_each(|c| {
-                if let Some(v) = c.value() {
-                    value.maybe_unset(v)
-                }
-            });
-
-            self.cells[i] = value;
+            // candidates that were already narrowed down stay that way, but
+            // the user's notes on empty cells are left out of it
+            self.cells[i] = match self.cells[i] {
+                Cell::Empty => Cell::Maybe(self.candidates(i)),
+                Cell::Maybe(m) => Cell::Maybe(m & self.candidates(i)),
+                _ => continue,
+            };
         }
     }
 }
@@ -199,63 +279,24 @@ impl Board {
             self.cells[i].make_good();
         }
 
-        for i in 0..9 {
-            if !self.is_row_ok(i) {
-                for j in 0..9 {
-                    self.cells[i * 9 + j].make_bad();
-                }
-            }
-            if !self.is_col_ok(i) {
-                for j in 0..9 {
-                    self.cells[j * 9 + i].make_bad();
-                }
-            }
-            let square_off = ((i / 3) * 3, (i % 3) * 3);
-            if !self.is_square_ok(square_off) {
-                for j in 0..9 {
-                    let (i, j) = (j / 3 + square_off.0, j % 3 + square_off.1);
-                    self.cells[i * 9 + j].make_bad();
-                }
-            }
-        }
-    }
-
-    fn is_row_ok(&self, row: usize) -> bool {
-        self.check_idxes(self.row(row))
-    }
-
-    fn is_col_ok(&self, col: usize) -> bool {
-        self.check_idxes(self.col(col))
-    }
-
-    fn is_square_ok(&self, square_offset: (usize, usize)) -> bool {
-        self.check_idxes(self.square(square_offset))
-    }
-
-    fn check_idxes<'a>(&'a self, idxes: impl Iterator<Item = &'a Cell>) -> bool {
-        idxes
-            .fold([0; 9], |mut acc, c| {
-                if let Some(v) = c.value() {
+        for h in 0..HOUSES {
+            let idxes = house(h);
+            let counts = idxes.iter().fold([0; 9], |mut acc, &i| {
+                if let Some(v) = self.cells[i].value() {
                     acc[v as usize - 1] += 1;
                 }
                 acc
-            })
-            .into_iter()
-            .all(|x| x < 2)
-    }
-
-    fn row<'a>(&'a self, row: usize) -> impl Iterator<Item = &'a Cell> {
-        (0..9).map(move |i| (row, i)).map(|i| &self[i])
-    }
-
-    fn col<'a>(&'a self, col: usize) -> impl Iterator<Item = &'a Cell> {
-        (0..9).map(move |i| (i, col)).map(|i| &self[i])
-    }
+            });
 
-    fn square<'a>(&'a self, square_offset: (usize, usize)) -> impl Iterator<Item = &'a Cell> {
-        (0..9)
-            .map(move |i| (i / 3 + square_offset.0, i % 3 + square_offset.1))
-            .map(|i| &self[i])
+            self.bad_houses[h] = counts.iter().any(|&x| x > 1);
+            for i in idxes {
+                if let Some(v) = self.cells[i].value() {
+                    if counts[v as usize - 1] > 1 {
+                        self.cells[i].make_bad();
+                    }
+                }
+            }
+        }
     }
 }
 
@@ -298,9 +339,9 @@ impl FromStr for Board {
                 match c {
                     '1'..='9' => {
                         if i > 8 || j > 8 {
-                            return Err(format!(
-                                "Expected max 9 characters per line and max 9 lines"
-                            ));
+                            return Err(
+                                "Expected max 9 characters per line and max 9 lines".to_string()
+                            );
                         }
                         board[(i, j)] = Cell::Good(c as u8 - b'0');
                     }
@@ -314,3 +355,109 @@ impl FromStr for Board {
         Ok(board)
     }
 }
+
+impl Board {
+    /// Reads the 81 character format: rows one after another, with `.` or
+    /// `0` for empty spaces
+    pub fn from_line(line: &str) -> Result<Self, String> {
+        let mut board = Board::default();
+        if line.chars().count() != 81 {
+            return Err(format!(
+                "Expected 81 characters, got {}",
+                line.chars().count()
+            ));
+        }
+
+        for (i, c) in line.chars().enumerate() {
+            match c {
+                '1'..='9' => board[i] = Cell::Good(c as u8 - b'0'),
+                '.' | '0' => (),
+                _ => return Err(format!("Unexpected character {}", c)),
+            }
+        }
+        board.calc_cell_states();
+
+        Ok(board)
+    }
+
+    /// Writes the 81 character format, with `.` for spaces without a value
+    pub fn to_line(&self) -> String {
+        self.cells
+            .iter()
+            .map(|c| match c.value() {
+                Some(v) => (b'0' + v) as char,
+                None => '.',
+            })
+            .collect()
+    }
+}
+
+/// Reads a file of puzzles, either one per line in the 81 character format
+/// or in the multi-line format. Multi-line puzzles are 9 lines each, and
+/// blank lines between them are skipped. Blank lines before the first one
+/// are its empty rows
+pub fn parse_collection(s: &str) -> Result<Vec<Board>, String> {
+    Collection::new(s.as_bytes()).collect()
+}
+
+/// Reads puzzles one at a time in the formats of `parse_collection`. The
+/// first puzzle decides which format the rest are in
+pub struct Collection<R> {
+    lines: io::Lines<R>,
+    multi_line: Option<bool>,
+    read: usize,
+}
+
+impl<R: BufRead> Collection<R> {
+    pub fn new(r: R) -> Self {
+        Self {
+            lines: r.lines(),
+            multi_line: None,
+            read: 0,
+        }
+    }
+
+    fn next_line(&mut self) -> Option<Result<String, String>> {
+        self.lines.next().map(|l| l.map_err(|e| e.to_string()))
+    }
+}
+
+impl<R: BufRead> Iterator for Collection<R> {
+    type Item = Result<Board, String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // blank lines before the first puzzle may be its first rows, with
+        // their spaces trimmed
+        let mut blank = 0;
+        let first = loop {
+            match self.next_line()? {
+                // a line of spaces is an empty row of a multi-line puzzle
+                Ok(l) if l.is_empty() => blank += 1,
+                Ok(l) if l.trim().is_empty() && self.multi_line == Some(false) => continue,
+                l => break l,
+            }
+        };
+        let leading = if self.read == 0 { blank } else { 0 };
+        self.read += 1;
+
+        let board = first.and_then(|first| {
+            let multi_line = *self
+                .multi_line
+                .get_or_insert(first.trim().chars().count() != 81);
+            if !multi_line {
+                return Board::from_line(first.trim());
+            }
+
+            let mut rows = vec![String::new(); leading];
+            rows.push(first);
+            while rows.len() < 9 {
+                match self.next_line() {
+                    Some(l) => rows.push(l?),
+                    None => break,
+                }
+            }
+            rows.join("\n").parse()
+        });
+        Some(board.map_err(|e| format!("Puzzle {}: {}", self.read, e)))
+    }
+}
@@ -0,0 +1,505 @@
+use super::{parse_collection, Board, Cell};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// File formats puzzles can be read from and written to
+#[derive(PartialEq, Eq, Clone, Copy, Debug, clap::ValueEnum)]
+pub enum Format {
+    /// 81 characters per puzzle, `.` or `0` for empty spaces
+    Line,
+    /// 9 lines per puzzle, spaces for empty spaces, as in `example/`
+    Grid,
+    /// SadMan Software: 9 lines of 9 characters, `.` for empty spaces and
+    /// `#` comment lines before them
+    Sdk,
+    /// SadMan Software extended: 9 lines of 9 space separated cells, each a
+    /// digit, `u` and a digit placed by the player, or its candidates
+    Sdx,
+    /// Simple Sudoku: `.` for empty spaces, boxes split by `|` and
+    /// `---+---+---`, framed by `*-----------*` or not
+    Ss,
+    /// HoDoKu's pencil mark grid: every cell's candidates in wide columns.
+    /// As in `Sdx`, a space with one candidate left reads back as filled
+    Hodoku,
+    /// Pencil marks as posted on forums: the same wide columns, with every
+    /// space's candidates in brackets, so one candidate isn't a filled space.
+    /// Reads rows of digits and bracketed candidates with or without borders
+    Marks,
+    /// One JSON object per puzzle and line, with its rows as arrays of
+    /// digits, 0 for empty spaces and arrays for candidates
+    Json,
+}
+
+impl Format {
+    /// The format a file extension stands for, if it stands for one
+    pub fn from_path(path: &str) -> Option<Self> {
+        let ext = Path::new(path).extension()?.to_str()?.to_lowercase();
+        match ext.as_str() {
+            "sdk" => Some(Format::Sdk),
+            "sdx" => Some(Format::Sdx),
+            "ss" => Some(Format::Ss),
+            "json" | "jsonl" => Some(Format::Json),
+            _ => None,
+        }
+    }
+
+    /// Guesses the format of a file's contents. `.sdx` files look too much
+    /// like the others to be told apart
+    pub fn detect(s: &str) -> Self {
+        let first = s.lines().map(str::trim).find(|l| !l.is_empty());
+        match first {
+            Some(l) if l.starts_with('{') => Format::Json,
+            _ if s.contains('[') => Format::Marks,
+            // Simple Sudoku can be framed by `*-----------*` too, but its
+            // rows are one character per cell, without spaces
+            Some(l) if l.starts_with("*-") && framed_ss(s) => Format::Ss,
+            Some(l) if l.starts_with(".-") || l.starts_with("*-") => Format::Hodoku,
+            Some(l) if l.starts_with('#') => Format::Sdk,
+            Some(l) if l.contains('|') || l.starts_with("---") => Format::Ss,
+            Some(l) if l.chars().count() == 81 => Format::Line,
+            Some(l) if l.chars().count() == 9 && l.contains('.') => Format::Sdk,
+            _ => Format::Grid,
+        }
+    }
+
+    /// Reads every puzzle of a file in this format. Formats meant for one
+    /// puzzle take several separated by blank lines
+    pub fn parse(&self, s: &str) -> Result<Vec<Board>, String> {
+        match self {
+            Format::Line | Format::Grid => parse_collection(s),
+            Format::Json => lines(s)
+                .enumerate()
+                .map(|(i, l)| {
+                    let b = serde_json::from_str::<JsonBoard>(l).map_err(|e| e.to_string());
+                    b.and_then(JsonBoard::board)
+                        .map_err(|e| format!("Puzzle {}: {}", i + 1, e))
+                })
+                .collect(),
+            _ => blocks(s)
+                .iter()
+                .enumerate()
+                .map(|(i, rows)| {
+                    self.parse_rows(rows)
+                        .map_err(|e| format!("Puzzle {}: {}", i + 1, e))
+                })
+                .collect(),
+        }
+    }
+
+    /// Writes one puzzle, ending in a newline
+    pub fn write(&self, b: &Board) -> String {
+        match self {
+            Format::Line => b.to_line() + "\n",
+            Format::Grid | Format::Sdk | Format::Ss => {
+                let (empty, band) = match self {
+                    Format::Grid => (' ', None),
+                    Format::Sdk => ('.', None),
+                    _ => ('.', Some("---+---+---")),
+                };
+                let mut out = String::new();
+                for r in 0..9 {
+                    if r > 0 && r % 3 == 0 {
+                        if let Some(band) = band {
+                            out += &format!("{}\n", band);
+                        }
+                    }
+                    for c in 0..9 {
+                        if self == &Format::Ss && c > 0 && c % 3 == 0 {
+                            out.push('|');
+                        }
+                        out.push(b[(r, c)].value().map_or(empty, |v| (b'0' + v) as char));
+                    }
+                    // trimmed like the files in example/, but an empty row
+                    // is kept as spaces so it isn't read as a blank line
+                    if self == &Format::Grid && !out.ends_with(&" ".repeat(9)) {
+                        out.truncate(out.trim_end_matches(' ').len());
+                    }
+                    out.push('\n');
+                }
+                out
+            }
+            Format::Sdx => (0..9)
+                .map(|r| {
+                    let cells = (0..9).map(|c| candidates(b, r * 9 + c, false));
+                    cells.collect::<Vec<_>>().join(" ") + "\n"
+                })
+                .collect(),
+            Format::Hodoku => write_marks(b, false),
+            Format::Marks => write_marks(b, true),
+            Format::Json => serde_json::to_string(&JsonBoard::new(b)).unwrap() + "\n",
+        }
+    }
+
+    /// Writes several puzzles, with blank lines between them where the
+    /// format needs them to be told apart
+    pub fn write_all(&self, boards: &[Board]) -> String {
+        let sep = match self {
+            Format::Line | Format::Json => "",
+            _ => "\n",
+        };
+        boards
+            .iter()
+            .map(|b| self.write(b))
+            .collect::<Vec<_>>()
+            .join(sep)
+    }
+
+    /// Whether the format keeps the candidates of empty spaces
+    pub fn has_candidates(&self) -> bool {
+        matches!(
+            self,
+            Format::Sdx | Format::Hodoku | Format::Marks | Format::Json
+        )
+    }
+
+    /// Reads a puzzle from its lines in one of the block formats
+    fn parse_rows(&self, rows: &[&str]) -> Result<Board, String> {
+        let cells = match self {
+            Format::Sdk => rows
+                .iter()
+                .filter(|l| !l.starts_with('#'))
+                .flat_map(|l| l.chars())
+                .map(given)
+                .collect::<Result<Vec<_>, _>>()?,
+            Format::Ss => rows
+                .iter()
+                // frame and separator lines have no cells
+                .filter(|l| l.chars().any(|c| c == '.' || c.is_ascii_digit()))
+                .flat_map(|l| l.chars().filter(|&c| c != '|'))
+                .map(given)
+                .collect::<Result<Vec<_>, _>>()?,
+            Format::Sdx => rows
+                .iter()
+                .flat_map(|l| l.split_whitespace())
+                .map(|t| match t.strip_prefix('u') {
+                    Some(v) => token(v).and_then(|c| match c {
+                        Cell::Good(_) => Ok(c),
+                        _ => Err(format!("Expected a digit after u, got {}", v)),
+                    }),
+                    None => token(t),
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+            Format::Hodoku | Format::Marks => rows
+                .iter()
+                // border lines have no digits
+                .filter(|l| l.chars().any(|c| c.is_ascii_digit()))
+                .flat_map(|l| l.split(|c: char| c == '|' || c.is_whitespace()))
+                .filter(|t| !t.is_empty())
+                .map(token)
+                .collect::<Result<Vec<_>, _>>()?,
+            _ => unreachable!(),
+        };
+        from_cells(cells)
+    }
+}
+
+/// Whether the rows under a `*---*` frame line are Simple Sudoku's
+fn framed_ss(s: &str) -> bool {
+    let row = lines(s).nth(1).unwrap_or("");
+    row.starts_with('|') && !row.contains(char::is_whitespace)
+}
+
+/// Lines that aren't blank
+fn lines(s: &str) -> impl Iterator<Item = &str> {
+    s.lines().map(str::trim).filter(|l| !l.is_empty())
+}
+
+/// Groups of lines separated by blank ones
+fn blocks(s: &str) -> Vec<Vec<&str>> {
+    let mut blocks = vec![vec![]];
+    for l in s.lines().map(str::trim) {
+        match blocks.last_mut().unwrap() {
+            b if l.is_empty() && !b.is_empty() => blocks.push(vec![]),
+            _ if l.is_empty() => (),
+            b => b.push(l),
+        }
+    }
+    blocks.retain(|b| !b.is_empty());
+    blocks
+}
+
+/// A cell of the formats with one character per cell
+fn given(c: char) -> Result<Cell, String> {
+    match c {
+        '1'..='9' => Ok(Cell::Good(c as u8 - b'0')),
+        '.' | '0' | 'x' | 'X' => Ok(Cell::Empty),
+        _ => Err(format!("Unexpected character {}", c)),
+    }
+}
+
+/// A cell written as its digit, or as all of its candidates, in brackets
+/// or not
+fn token(t: &str) -> Result<Cell, String> {
+    if t == "." || t == "0" {
+        return Ok(Cell::Empty);
+    }
+    let bracketed = t.strip_prefix('[').and_then(|t| t.strip_suffix(']'));
+    let digits = bracketed.unwrap_or(t);
+    if let Some(c) = digits.chars().find(|c| !('1'..='9').contains(c)) {
+        return Err(format!("Unexpected character {}", c));
+    }
+
+    let mask = digits.bytes().fold(0, |m, c| m | 1 << (c - b'0'));
+    match digits.len() {
+        1 if bracketed.is_none() => Ok(Cell::Good(digits.as_bytes()[0] - b'0')),
+        _ => Ok(Cell::Maybe(mask)),
+    }
+}
+
+/// A cell's digit, or the candidates it has left
+fn candidates(b: &Board, idx: usize, brackets: bool) -> String {
+    let mask = match b[idx] {
+        Cell::Maybe(m) => m & b.candidates(idx),
+        Cell::Empty => b.candidates(idx),
+        c => return c.to_string(),
+    };
+    let digits = (1..=9u8)
+        .filter(|v| mask & 1 << v != 0)
+        .map(|v| (b'0' + v) as char)
+        .collect::<String>();
+    match digits {
+        _ if brackets => format!("[{}]", digits),
+        // a space without candidates still has to take up a column
+        s if s.is_empty() => "0".to_string(),
+        s => s,
+    }
+}
+
+fn from_cells(cells: Vec<Cell>) -> Result<Board, String> {
+    let cells: [Cell; 81] = cells
+        .try_into()
+        .map_err(|c: Vec<Cell>| format!("Expected 81 cells, got {}", c.len()))?;
+
+    let mut board = Board {
+        cells,
+        ..Default::default()
+    };
+    board.calc_cell_states();
+    Ok(board)
+}
+
+/// The wide column pencil mark grid of HoDoKu
+fn write_marks(b: &Board, brackets: bool) -> String {
+    let cells = (0..81)
+        .map(|i| candidates(b, i, brackets))
+        .collect::<Vec<_>>();
+    let widths = (0..9)
+        .map(|c| (0..9).map(|r| cells[r * 9 + c].len()).max().unwrap())
+        .collect::<Vec<_>>();
+    // a box is its columns with two spaces between and one at either end
+    let box_width = |c: usize| (c * 3..c * 3 + 3).map(|c| widths[c]).sum::<usize>() + 6;
+
+    let border = |left: char, mid: char, right: char| {
+        let dashes = (0..3).map(|c| "-".repeat(box_width(c))).collect::<Vec<_>>();
+        format!("{}{}{}\n", left, dashes.join(&mid.to_string()), right)
+    };
+
+    let mut out = border('.', '.', '.');
+    for r in 0..9 {
+        if r > 0 && r % 3 == 0 {
+            out += &border(':', '+', ':');
+        }
+        out.push('|');
+        for c in 0..9 {
+            out += &format!(" {:w$} ", cells[r * 9 + c], w = widths[c]);
+            if c % 3 == 2 {
+                out.push('|');
+            }
+        }
+        out.push('\n');
+    }
+    out + &border('\'', '\'', '\'')
+}
+
+#[derive(Serialize, Deserialize)]
+struct JsonBoard {
+    rows: Vec<Vec<JsonCell>>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum JsonCell {
+    /// 0 for an empty space
+    Value(u8),
+    Candidates(Vec<u8>),
+}
+
+impl JsonBoard {
+    fn new(b: &Board) -> Self {
+        let cell = |c: &Cell| match c {
+            Cell::Maybe(_) => JsonCell::Candidates(c.maybe_values()),
+            c => JsonCell::Value(c.value().unwrap_or(0)),
+        };
+        Self {
+            rows: (0..9)
+                .map(|r| (0..9).map(|c| cell(&b[(r, c)])).collect())
+                .collect(),
+        }
+    }
+
+    fn board(self) -> Result<Board, String> {
+        let digit = |v: u8| match v {
+            1..=9 => Ok(v),
+            _ => Err(format!("{} isn't a digit from 1 to 9", v)),
+        };
+        let cells = self
+            .rows
+            .into_iter()
+            .flatten()
+            .map(|c| match c {
+                JsonCell::Value(0) => Ok(Cell::Empty),
+                JsonCell::Value(v) => digit(v).map(Cell::Good),
+                JsonCell::Candidates(vs) => vs
+                    .into_iter()
+                    .try_fold(0, |m, v| digit(v).map(|v| m | 1 << v))
+                    .map(Cell::Maybe),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        from_cells(cells)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::ValueEnum;
+
+    fn hard() -> Vec<Board> {
+        let s = std::fs::read_to_string("example/hard.txt").unwrap();
+        Format::Line.parse(&s).unwrap()
+    }
+
+    fn cells(b: &Board) -> Vec<Cell> {
+        (0..81).map(|i| b[i]).collect()
+    }
+
+    #[test]
+    fn round_trips() {
+        let boards = hard();
+        for f in Format::value_variants() {
+            let text = f.write_all(&boards);
+            let back = f.parse(&text).unwrap();
+            assert_eq!(back.len(), boards.len(), "{:?}", f);
+            assert_eq!(f.write_all(&back), text, "{:?}", f);
+
+            for (i, (a, b)) in boards.iter().zip(&back).enumerate() {
+                if !f.has_candidates() {
+                    assert!(cells(a) == cells(b), "{:?} puzzle {}", f, i);
+                    continue;
+                }
+                // empty spaces read back as their candidates, and in Sdx and
+                // Hodoku as filled if only one is left
+                let fixed = matches!(f, Format::Marks | Format::Json);
+                for j in 0..81 {
+                    match a[j].value() {
+                        Some(_) => assert!(a[j] == b[j], "{:?} puzzle {}", f, i),
+                        None if fixed => assert!(b[j].value().is_none(), "{:?} {}", f, i),
+                        None => (),
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn round_trips_candidates() {
+        let mut boards = hard();
+        boards.iter_mut().for_each(Board::init_maybe);
+        for f in [Format::Marks, Format::Json] {
+            let back = f.parse(&f.write_all(&boards)).unwrap();
+            for (i, (a, b)) in boards.iter().zip(&back).enumerate() {
+                assert!(cells(a) == cells(b), "{:?} puzzle {}", f, i);
+            }
+        }
+    }
+
+    #[test]
+    fn detects() {
+        let boards = hard();
+        for &f in Format::value_variants() {
+            // reads as Hodoku, which takes the same rows
+            if f == Format::Sdx {
+                continue;
+            }
+            assert_eq!(Format::detect(&f.write_all(&boards)), f);
+        }
+    }
+
+    #[test]
+    fn brackets_keep_one_candidate() {
+        assert!(token("5").unwrap() == Cell::Good(5));
+        assert!(token("[5]").unwrap() == Cell::Maybe(1 << 5));
+        assert!(token("[15]").unwrap() == Cell::Maybe(1 << 1 | 1 << 5));
+        assert!(token("15").unwrap() == Cell::Maybe(1 << 1 | 1 << 5));
+        assert!(token("0").unwrap() == Cell::Empty);
+    }
+
+    #[test]
+    fn space_without_candidates() {
+        let s = "12345678.\n........9\n".to_string() + &".........\n".repeat(7);
+        let b = Format::Sdk.parse(&s).unwrap()[0];
+        assert_eq!(candidates(&b, 8, false), "0");
+        assert_eq!(candidates(&b, 8, true), "[]");
+
+        for f in [Format::Sdx, Format::Hodoku] {
+            let back = f.parse(&f.write(&b)).unwrap()[0];
+            assert!(back[8] == Cell::Empty, "{:?}", f);
+        }
+        let back = Format::Marks.parse(&Format::Marks.write(&b)).unwrap()[0];
+        assert!(back[8] == Cell::Maybe(0));
+    }
+
+    #[test]
+    fn keeps_empty_grid_rows() {
+        let mut boards = hard();
+        boards.truncate(2);
+        boards[0] = Format::Line
+            .parse(&(".".repeat(9) + &boards[0].to_line()[9..]))
+            .unwrap()[0];
+
+        let text = Format::Grid.write_all(&boards);
+        assert!(text.starts_with(&" ".repeat(9)));
+        let back = Format::Grid.parse(&text).unwrap();
+        assert_eq!(back.len(), 2);
+        assert!(cells(&back[0]) == cells(&boards[0]));
+    }
+
+    #[test]
+    fn reads_blank_first_row() {
+        // trimmed like the other examples, so its first line is empty
+        let s = std::fs::read_to_string("example/3.txt").unwrap();
+        assert_eq!(Format::detect(&s), Format::Grid);
+        let b = Format::Grid.parse(&s).unwrap()[0];
+        assert_eq!(
+            b.to_line(),
+            ".........6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..53...8..79"
+        );
+    }
+
+    #[test]
+    fn reads_framed_ss() {
+        let framed = "\
+*-----------*
+|53.|.7.|...|
+|6..|195|...|
+|.98|...|.6.|
+|---+---+---|
+|8..|.6.|..3|
+|4..|8.3|..1|
+|7..|.2.|..6|
+|---+---+---|
+|.6.|...|28.|
+|...|419|..5|
+|...|.8.|.79|
+*-----------*
+";
+        assert_eq!(Format::detect(framed), Format::Ss);
+        let b = Format::Ss.parse(framed).unwrap()[0];
+        assert_eq!(
+            b.to_line(),
+            "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79"
+        );
+        let back = Format::Ss.parse(&Format::Ss.write(&b)).unwrap()[0];
+        assert!(cells(&back) == cells(&b));
+    }
+}
@@ -0,0 +1,291 @@
+use crate::ui::ThemeName;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::Deserialize;
+use std::{collections::HashMap, fmt::Display, path::PathBuf, str::FromStr};
+
+/// Settings read from `$XDG_CONFIG_HOME/sudoku-wfc/config.toml`
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Keys for each action, replacing its default ones
+    pub keys: HashMap<Action, Vec<String>>,
+    /// Falls back to mono if NO_COLOR is set, the default theme otherwise
+    pub theme: Option<ThemeName>,
+}
+
+impl Config {
+    pub fn default_path() -> Option<PathBuf> {
+        let dir = match std::env::var_os("XDG_CONFIG_HOME") {
+            Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+            _ => PathBuf::from(std::env::var_os("HOME")?).join(".config"),
+        };
+        Some(dir.join("sudoku-wfc").join("config.toml"))
+    }
+
+    /// Reads the config at `path`, or the default location if there is none.
+    /// A missing file at the default location is not an error
+    pub fn load(path: Option<&str>) -> Result<Self, String> {
+        let path = match path {
+            Some(p) => PathBuf::from(p),
+            None => match Self::default_path() {
+                Some(p) if p.exists() => p,
+                _ => return Ok(Self::default()),
+            },
+        };
+
+        let data = std::fs::read_to_string(&path)
+            .map_err(|e| format!("Couldn't read {}: {}", path.display(), e))?;
+        toml::from_str(&data).map_err(|e| format!("Bad config {}: {}", path.display(), e.message()))
+    }
+}
+
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Action {
+    Help,
+    Up,
+    Down,
+    Left,
+    Right,
+    Next,
+    Erase,
+    Solve,
+    ClearSolved,
+    ClearAll,
+    UnderlineConflicts,
+    ExportSvg,
+    ExportMarks,
+    Quit,
+}
+
+impl Action {
+    /// In the order they are listed in the help
+    pub const ALL: [Action; 14] = [
+        Action::Help,
+        Action::Up,
+        Action::Down,
+        Action::Left,
+        Action::Right,
+        Action::Next,
+        Action::Erase,
+        Action::Solve,
+        Action::ClearSolved,
+        Action::ClearAll,
+        Action::UnderlineConflicts,
+        Action::ExportSvg,
+        Action::ExportMarks,
+        Action::Quit,
+    ];
+
+    fn description(&self) -> &'static str {
+        match self {
+            Action::Help => "toggle this message",
+            Action::Up => "move up",
+            Action::Down => "move down",
+            Action::Left => "move left",
+            Action::Right => "move right",
+            Action::Next => "go to next space",
+            Action::Erase => "clear current space",
+            Action::Solve => "start solving",
+            Action::ClearSolved => "clear solved spaces",
+            Action::ClearAll => "clear entire board",
+            Action::UnderlineConflicts => "underline houses with conflicts",
+            Action::ExportSvg => "export board as SVG",
+            Action::ExportMarks => "export pencil marks",
+            Action::Quit => "quit",
+        }
+    }
+
+    fn default_keys(&self) -> &'static [&'static str] {
+        match self {
+            Action::Help => &["?"],
+            Action::Up => &["up", "k"],
+            Action::Down => &["down", "j"],
+            Action::Left => &["left", "h"],
+            Action::Right => &["right", "l"],
+            Action::Next => &["tab"],
+            Action::Erase => &["backspace", "delete"],
+            Action::Solve => &["s"],
+            Action::ClearSolved => &["c"],
+            Action::ClearAll => &["C"],
+            Action::UnderlineConflicts => &["u"],
+            Action::ExportSvg => &["e"],
+            Action::ExportMarks => &["m"],
+            Action::Quit => &["q", "esc", "ctrl-c", "ctrl-d"],
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Copy)]
+pub struct Key {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl Key {
+    fn matches(&self, k: &KeyEvent) -> bool {
+        // shift is part of the character for letters and symbols
+        let modifiers = match k.code {
+            KeyCode::Char(_) => k.modifiers - KeyModifiers::SHIFT,
+            _ => k.modifiers,
+        };
+        self.code == k.code && self.modifiers == modifiers
+    }
+}
+
+impl FromStr for Key {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = s;
+        // a lone "-" is the minus key, not a modifier separator
+        while let Some((m, r)) = rest.split_once('-').filter(|(_, r)| !r.is_empty()) {
+            modifiers |= match m.to_lowercase().as_str() {
+                "ctrl" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                _ => return Err(format!("Unknown modifier {} in key {}", m, s)),
+            };
+            rest = r;
+        }
+
+        let code = match rest.to_lowercase().as_str() {
+            _ if rest.chars().count() == 1 => KeyCode::Char(rest.chars().next().unwrap()),
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "tab" => KeyCode::Tab,
+            "backspace" => KeyCode::Backspace,
+            "delete" => KeyCode::Delete,
+            "enter" => KeyCode::Enter,
+            "esc" => KeyCode::Esc,
+            "space" => KeyCode::Char(' '),
+            f if f.starts_with('f') && f[1..].parse::<u8>().is_ok() => {
+                KeyCode::F(f[1..].parse().unwrap())
+            }
+            _ => return Err(format!("Unknown key {}", s)),
+        };
+
+        if matches!(code, KeyCode::Char('1'..='9')) && modifiers.is_empty() {
+            return Err(format!("Key {} is reserved for entering digits", s));
+        }
+
+        Ok(Self { code, modifiers })
+    }
+}
+
+impl Display for Key {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "ctrl-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "alt-")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::F(n) => write!(f, "f{}", n),
+            KeyCode::Backspace => write!(f, "backspace"),
+            KeyCode::Delete => write!(f, "delete"),
+            KeyCode::Enter => write!(f, "enter"),
+            KeyCode::Esc => write!(f, "esc"),
+            KeyCode::Tab => write!(f, "tab"),
+            KeyCode::Up => write!(f, "up"),
+            KeyCode::Down => write!(f, "down"),
+            KeyCode::Left => write!(f, "left"),
+            KeyCode::Right => write!(f, "right"),
+            _ => write!(f, "?"),
+        }
+    }
+}
+
+/// Which keys trigger which action
+pub struct Keybinds {
+    binds: Vec<(Action, Vec<Key>)>,
+}
+
+impl Default for Keybinds {
+    fn default() -> Self {
+        Self::new(&HashMap::new()).unwrap()
+    }
+}
+
+impl Keybinds {
+    /// Default bindings with the actions in `overrides` rebound. A key
+    /// taken by an override is removed from other actions' defaults
+    pub fn new(overrides: &HashMap<Action, Vec<String>>) -> Result<Self, String> {
+        let parse = |keys: &[String]| {
+            keys.iter()
+                .map(|k| k.parse())
+                .collect::<Result<Vec<Key>, _>>()
+        };
+
+        let mut overridden = HashMap::new();
+        for (action, keys) in overrides {
+            overridden.insert(*action, parse(keys)?);
+        }
+        let taken = overridden.values().flatten().copied().collect::<Vec<_>>();
+
+        let binds = Action::ALL
+            .into_iter()
+            .map(|a| match overridden.remove(&a) {
+                Some(keys) => (a, keys),
+                None => {
+                    let keys = a
+                        .default_keys()
+                        .iter()
+                        .map(|k| k.parse().unwrap())
+                        .filter(|k| !taken.contains(k))
+                        .collect();
+                    (a, keys)
+                }
+            })
+            .collect();
+
+        Ok(Self { binds })
+    }
+
+    pub fn action(&self, k: &KeyEvent) -> Option<Action> {
+        self.binds
+            .iter()
+            .find(|(_, keys)| keys.iter().any(|key| key.matches(k)))
+            .map(|(a, _)| *a)
+    }
+
+    /// Help text listing the active bindings
+    pub fn help(&self) -> String {
+        let mut lines = vec![];
+        let mut line = |keys: &str, desc: &'static str| lines.push((keys.to_string(), desc));
+
+        for (action, keys) in &self.binds {
+            if keys.is_empty() {
+                continue;
+            }
+
+            let keys = keys
+                .iter()
+                .map(|k| k.to_string())
+                .collect::<Vec<_>>()
+                .join(", ");
+            line(&keys, action.description());
+            if *action == Action::Next {
+                line("1..9", "set current space");
+            }
+        }
+        line("click", "select space, toggle the note under it");
+
+        // keys line up in a column as wide as the longest of them
+        let width = lines
+            .iter()
+            .map(|(k, _)| k.chars().count())
+            .max()
+            .unwrap_or(0);
+        let mut help = "Keybinds:\r\n".to_string();
+        for (keys, desc) in lines {
+            help += &format!("  {:<width$} -> {}\r\n", keys, desc);
+        }
+        help
+    }
+}
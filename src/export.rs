@@ -0,0 +1,4 @@
+pub mod book;
+pub mod cast;
+pub mod svg;
+pub mod trace;
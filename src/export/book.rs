@@ -0,0 +1,99 @@
+use super::svg;
+use crate::{
+    board::Board,
+    grade::{grade, Difficulty},
+    wfc::Wfc,
+};
+use std::fmt::Write;
+
+const STYLE: &str = "
+@page { size: A4; margin: 12mm; }
+body { font-family: sans-serif; margin: 0; }
+h1 { text-align: center; font-size: 20pt; margin: 0 0 8mm; }
+.page { display: grid; gap: 8mm; break-after: page; page-break-after: always; }
+.page:last-child { break-after: auto; page-break-after: auto; }
+figure { margin: 0; break-inside: avoid; page-break-inside: avoid; }
+figcaption { display: flex; justify-content: space-between; font-size: 11pt; margin-bottom: 2mm; }
+figure svg { width: 100%; height: auto; display: block; }
+.none { border: 1px solid black; aspect-ratio: 1; display: flex; align-items: center; justify-content: center; }
+@media screen { .page { max-width: 190mm; margin: 0 auto 16mm; } }
+";
+
+struct Entry {
+    puzzle: Board,
+    difficulty: Difficulty,
+    solution: Option<Board>,
+}
+
+/// Self-contained HTML document with `per_page` puzzles to a printed page,
+/// each numbered and labelled with its difficulty, and the solutions after
+/// all the puzzles
+pub fn render(puzzles: &[Board], per_page: usize, title: &str, wfc: &mut Wfc) -> String {
+    let per_page = per_page.max(1);
+    let columns = match per_page {
+        1 => 1,
+        2..=4 => 2,
+        _ => 3,
+    };
+
+    let entries = puzzles
+        .iter()
+        .map(|p| Entry {
+            puzzle: *p,
+            difficulty: grade(p),
+            solution: wfc.solve(*p),
+        })
+        .collect::<Vec<_>>();
+
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n\
+        <style>{}.page {{ grid-template-columns: repeat({}, 1fr); }}\n</style>\n</head>\n<body>\n",
+        escape(title),
+        STYLE,
+        columns,
+    );
+
+    pages(&mut html, &entries, per_page, title, |e| Some(e.puzzle));
+    pages(&mut html, &entries, per_page, "Solutions", |e| e.solution);
+
+    html += "</body>\n</html>\n";
+    html
+}
+
+fn pages(
+    html: &mut String,
+    entries: &[Entry],
+    per_page: usize,
+    heading: &str,
+    board: impl Fn(&Entry) -> Option<Board>,
+) {
+    for (p, page) in entries.chunks(per_page).enumerate() {
+        if p == 0 {
+            let _ = writeln!(html, "<h1>{}</h1>", escape(heading));
+        }
+
+        *html += "<section class=\"page\">\n";
+        for (i, e) in page.iter().enumerate() {
+            let n = p * per_page + i + 1;
+            let _ = writeln!(
+                html,
+                "<figure>\n<figcaption><span>#{}</span><span>{}</span></figcaption>",
+                n,
+                e.difficulty.label(),
+            );
+            match board(e) {
+                Some(b) => *html += &svg::render(&b, svg::CELL_SIZE),
+                None => *html += "<div class=\"none\">No solution</div>\n",
+            }
+            *html += "</figure>\n";
+        }
+        *html += "</section>\n";
+    }
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
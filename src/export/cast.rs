@@ -0,0 +1,54 @@
+use crate::app::headless::Clock;
+use std::io::{self, Write};
+
+/// Turns what the Ui draws into an asciinema v2 recording. Everything
+/// written between two flushes becomes one output event, stamped with the
+/// session's clock
+pub struct Cast<W: Write> {
+    out: W,
+    buf: Vec<u8>,
+    clock: Clock,
+}
+
+impl<W: Write> Cast<W> {
+    pub fn new(mut out: W, (width, height): (u16, u16), clock: Clock) -> io::Result<Self> {
+        let header = serde_json::json!({
+            "version": 2,
+            "width": width,
+            "height": height,
+            "env": { "TERM": "xterm-256color" },
+        });
+        writeln!(out, "{}", header)?;
+
+        Ok(Self {
+            out,
+            buf: vec![],
+            clock,
+        })
+    }
+}
+
+impl<W: Write> Write for Cast<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buf.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if self.buf.is_empty() {
+            return Ok(());
+        }
+
+        let time = self.clock.get().as_secs_f64();
+        let data = String::from_utf8_lossy(&self.buf);
+        writeln!(self.out, "{}", serde_json::json!([time, "o", data]))?;
+        self.buf.clear();
+        self.out.flush()
+    }
+}
+
+impl<W: Write> Drop for Cast<W> {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
@@ -0,0 +1,102 @@
+use crate::board::{Board, Cell};
+use std::fmt::Write;
+
+/// Pixels per cell in standalone SVGs
+pub const CELL_SIZE: f32 = 48.0;
+
+const THIN: f32 = 1.0;
+const THICK: f32 = 3.0;
+
+/// Standalone SVG document of the board: givens, solved digits, candidate
+/// notes and conflicts, with thick lines around squares
+pub fn render(b: &Board, cell: f32) -> String {
+    let size = cell * 9.0 + THICK;
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{size}\" height=\"{size}\" \
+        viewBox=\"0 0 {size} {size}\" font-family=\"sans-serif\" text-anchor=\"middle\" \
+        dominant-baseline=\"central\">\n"
+    );
+    svg += &format!("<rect width=\"{size}\" height=\"{size}\" fill=\"white\"/>\n");
+
+    let origin = THICK / 2.0;
+    for i in 0..81 {
+        let x = origin + (i % 9) as f32 * cell;
+        let y = origin + (i / 9) as f32 * cell;
+        cell_svg(&mut svg, b, i, x, y, cell);
+    }
+
+    // thin lines first so the thick ones are drawn over the crossings
+    for thick in [false, true] {
+        for i in 0..=9 {
+            if (i % 3 == 0) != thick {
+                continue;
+            }
+
+            let p = origin + i as f32 * cell;
+            let (end, width) = (origin + 9.0 * cell, if thick { THICK } else { THIN });
+            let color = if thick { "black" } else { "#999" };
+            let _ = writeln!(
+                svg,
+                "<path d=\"M{p} {s}V{end}M{s} {p}H{end}\" stroke=\"{color}\" \
+                stroke-width=\"{width}\" stroke-linecap=\"square\"/>",
+                s = origin,
+            );
+        }
+    }
+
+    svg += "</svg>\n";
+    svg
+}
+
+fn cell_svg(svg: &mut String, b: &Board, idx: usize, x: f32, y: f32, cell: f32) {
+    let (cx, cy) = (x + cell / 2.0, y + cell / 2.0);
+    let digit = |svg: &mut String, v: u8, fill: &str, weight: &str| {
+        let _ = writeln!(
+            svg,
+            "<text x=\"{cx}\" y=\"{cy}\" font-size=\"{:.1}\" font-weight=\"{weight}\" \
+            fill=\"{fill}\">{v}</text>",
+            cell * 0.65,
+        );
+    };
+    let background = |svg: &mut String, fill: &str| {
+        let _ = writeln!(
+            svg,
+            "<rect x=\"{x}\" y=\"{y}\" width=\"{cell}\" height=\"{cell}\" fill=\"{fill}\"/>"
+        );
+    };
+
+    let marks = |svg: &mut String, m: u16| {
+        for v in (1..=9).filter(|v| m & 1 << v != 0) {
+            let (row, col) = ((v - 1) / 3, (v - 1) % 3);
+            let _ = writeln!(
+                svg,
+                "<text x=\"{}\" y=\"{}\" font-size=\"{:.1}\" fill=\"#666\">{v}</text>",
+                x + cell * (col as f32 * 2.0 + 1.0) / 6.0,
+                y + cell * (row as f32 * 2.0 + 1.0) / 6.0,
+                cell * 0.25,
+            );
+        }
+    };
+
+    if b.is_dead(idx) {
+        background(svg, "#fdd");
+        let _ = writeln!(
+            svg,
+            "<text x=\"{cx}\" y=\"{cy}\" font-size=\"{:.1}\" fill=\"#c00\">×</text>",
+            cell * 0.65,
+        );
+        return;
+    }
+
+    match b[idx] {
+        Cell::Good(v) => digit(svg, v, "black", "bold"),
+        Cell::Bad(v) => {
+            background(svg, "#fdd");
+            digit(svg, v, "#c00", "bold");
+        }
+        Cell::Collapsed(v) => digit(svg, v, "#1a5fb4", "normal"),
+        // candidates, or the user's notes on an empty cell
+        Cell::Maybe(m) => marks(svg, m),
+        Cell::Empty => marks(svg, b.notes(idx)),
+    }
+}
@@ -0,0 +1,47 @@
+use crate::{
+    board::Board,
+    wfc::{Step, StepResult, Wfc},
+};
+use serde::Serialize;
+use std::io::{self, Write};
+
+#[derive(Serialize)]
+struct Line<'a> {
+    step: usize,
+    row: Option<usize>,
+    col: Option<usize>,
+    #[serde(flatten)]
+    outcome: &'a Step,
+}
+
+/// Solves the puzzle, writing every step of the search to `out` as one
+/// JSON object per line. Returns the solution if there is one
+pub fn write(mut b: Board, wfc: &mut Wfc, out: &mut impl Write) -> io::Result<Option<Board>> {
+    if !b.can_solve() {
+        return Ok(None);
+    }
+
+    b.init_maybe();
+    wfc.init(b);
+
+    for step in 1.. {
+        let res = wfc.step();
+
+        let outcome = wfc.last_step().unwrap();
+        let line = Line {
+            step,
+            row: outcome.cell.map(|c| c / 9),
+            col: outcome.cell.map(|c| c % 9),
+            outcome,
+        };
+        serde_json::to_writer(&mut *out, &line)?;
+        writeln!(out)?;
+
+        match res {
+            StepResult::Complete(b) => return Ok(Some(b)),
+            StepResult::InProgress(_) => (),
+            StepResult::Impossible | StepResult::GaveUp => return Ok(None),
+        }
+    }
+    unreachable!()
+}
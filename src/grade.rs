@@ -0,0 +1,79 @@
+use crate::board::{house, Board, HOUSES};
+
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug)]
+pub enum Difficulty {
+    /// Solved by filling in spaces with a single candidate
+    Easy,
+    /// Also needs values that fit in only one space of a house
+    Medium,
+    /// Needs guessing, or has no solution
+    Hard,
+}
+
+impl Difficulty {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Medium => "Medium",
+            Difficulty::Hard => "Hard",
+        }
+    }
+}
+
+/// Grades a puzzle by the simplest techniques that solve it without guessing
+pub fn grade(b: &Board) -> Difficulty {
+    if !b.can_solve() {
+        return Difficulty::Hard;
+    }
+
+    let mut b = *b;
+    b.init_maybe();
+    let mut hidden = false;
+
+    loop {
+        let uncollapsed = b.get_uncollapsed();
+        if uncollapsed.is_empty() {
+            break;
+        }
+        if uncollapsed.iter().any(|(_, c)| c.entropy() == 0) {
+            return Difficulty::Hard;
+        }
+
+        if let Some((i, c)) = uncollapsed.iter().find(|(_, c)| c.entropy() == 1) {
+            b.collapse(*i, c.maybe_values()[0]);
+            continue;
+        }
+
+        match hidden_single(&b) {
+            Some((i, v)) => {
+                b.collapse(i, v);
+                hidden = true;
+            }
+            None => return Difficulty::Hard,
+        }
+    }
+
+    if hidden {
+        Difficulty::Medium
+    } else {
+        Difficulty::Easy
+    }
+}
+
+/// A value that only one space of some house can still take
+fn hidden_single(b: &Board) -> Option<(usize, u8)> {
+    for h in 0..HOUSES {
+        let idxes = house(h);
+        for v in 1..=9 {
+            if idxes.iter().any(|&i| b[i].value() == Some(v)) {
+                continue;
+            }
+
+            let mut spaces = idxes.iter().filter(|&&i| b[i].maybe_get_num(v));
+            if let (Some(&i), None) = (spaces.next(), spaces.next()) {
+                return Some((i, v));
+            }
+        }
+    }
+    None
+}
@@ -0,0 +1,4 @@
+pub mod batch;
+pub mod board;
+pub mod grade;
+pub mod wfc;
@@ -1,11 +1,24 @@
-use app::App;
-use clap::Parser;
-use std::io;
+use app::{
+    headless::{Clock, Headless},
+    App,
+};
+use board::format::Format;
+use clap::{Parser, Subcommand};
+use config::Config;
+use export::cast::Cast;
+use std::{
+    fs::File,
+    io::{self, IsTerminal, Write},
+};
+use sudoku_wfc::{batch, board, grade, wfc};
+use ui::{Theme, ThemeName};
+use wfc::{parallel, Augment, CellChoice, Overlapping, Search, Wfc};
 
 mod app;
-mod board;
+mod bench;
+mod config;
+mod export;
 mod ui;
-mod wfc;
 
 /// Sudoku solver using the wave function collapse algorithm
 #[derive(Parser)]
@@ -13,16 +26,449 @@ struct Args {
     /// Optional path to file of initial values
     #[arg(short)]
     file: Option<String>,
+
+    /// Config file to use instead of $XDG_CONFIG_HOME/sudoku-wfc/config.toml
+    #[arg(long)]
+    config: Option<String>,
+
+    /// Color theme, overriding the one from the config file
+    #[arg(long)]
+    theme: Option<ThemeName>,
+
+    /// Run without a terminal, feeding in these key presses, and print the
+    /// final screen. `<wait>` lets the solver finish, `<tab>`, `<up>` etc.
+    /// name special keys
+    #[arg(long)]
+    script: Option<String>,
+
+    /// Record the screen of a --script run to an asciinema .cast file. The
+    /// script defaults to solving the puzzle
+    #[arg(long)]
+    record: Option<String>,
+
+    /// Terminal size for --script and --record, as WIDTHxHEIGHT
+    #[arg(long, default_value = "80x50", value_parser = parse_size)]
+    size: (u16, u16),
+
+    #[command(flatten)]
+    search: Search,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Export a puzzle as a standalone SVG
+    Svg {
+        /// Path to file of initial values
+        file: String,
+
+        /// Where to write the SVG, stdout if not given
+        #[arg(short)]
+        output: Option<String>,
+
+        /// Fill in the solution
+        #[arg(long)]
+        solve: bool,
+    },
+    /// Solve a puzzle without the UI, writing each search step as a line of JSON
+    Trace {
+        /// Path to file of initial values
+        file: String,
+
+        /// Where to write the steps, stdout if not given
+        #[arg(short)]
+        output: Option<String>,
+    },
+    /// Make a printable HTML book of puzzles with their solutions at the back
+    Book {
+        /// File of puzzles, one per line in the 81 character format or 9
+        /// lines each separated by blank lines
+        file: String,
+
+        /// Where to write the HTML, stdout if not given
+        #[arg(short)]
+        output: Option<String>,
+
+        /// Puzzles on each printed page
+        #[arg(long, default_value_t = 4)]
+        per_page: usize,
+
+        /// Title on the first page
+        #[arg(long, default_value = "Sudoku")]
+        title: String,
+    },
+    /// Solve every puzzle in a file and report how long it took and how
+    /// hard the search worked
+    Bench {
+        /// File of puzzles, one per line in the 81 character format or 9
+        /// lines each separated by blank lines
+        file: String,
+
+        /// Slowest puzzles to list
+        #[arg(long, default_value_t = 5)]
+        slowest: usize,
+
+        /// Threads to solve on, one per core if not given
+        #[arg(short, long)]
+        jobs: Option<usize>,
+    },
+    /// Solve every puzzle in a file, writing the solutions in the 81
+    /// character format in the same order. Puzzles without one are written
+    /// as they were, with a note on stderr
+    Solve {
+        /// File of puzzles, one per line in the 81 character format or 9
+        /// lines each separated by blank lines
+        file: String,
+
+        /// Where to write the solutions, stdout if not given
+        #[arg(short)]
+        output: Option<String>,
+
+        /// Threads to solve on, one per core if not given
+        #[arg(short, long)]
+        jobs: Option<usize>,
+
+        /// Solve one puzzle at a time, splitting its search across the
+        /// threads. Faster for a few very hard puzzles
+        #[arg(long)]
+        split: bool,
+
+        /// Write how many solutions each puzzle has instead, splitting the
+        /// search like --split
+        #[arg(long)]
+        count: bool,
+    },
+    /// Convert a file of puzzles from one format to another
+    Convert {
+        /// File of puzzles
+        file: String,
+
+        /// Where to write the puzzles, stdout if not given
+        #[arg(short)]
+        output: Option<String>,
+
+        /// Format of the file, from its extension or contents if not given
+        #[arg(long, value_enum)]
+        from: Option<Format>,
+
+        /// Format to write, from the output's extension or the 81 character
+        /// format if not given
+        #[arg(long, value_enum)]
+        to: Option<Format>,
+    },
+    /// Generate a map that looks like a sample up close, out of the NxN
+    /// patterns in it
+    Generate {
+        /// Text file with the sample, a small grid of characters
+        sample: String,
+
+        /// Where to write the map as plain text, the terminal if not given
+        #[arg(short)]
+        output: Option<String>,
+
+        /// Size of the patterns
+        #[arg(short, default_value_t = 3)]
+        n: usize,
+
+        /// Width of the map in characters
+        #[arg(long, default_value_t = 60)]
+        width: usize,
+
+        /// Height of the map in characters
+        #[arg(long, default_value_t = 20)]
+        height: usize,
+
+        /// Also use the patterns turned by quarter turns
+        #[arg(long)]
+        rotate: bool,
+
+        /// Also use the mirror images of the patterns
+        #[arg(long)]
+        reflect: bool,
+
+        /// Threads to split the search between, one search on this thread
+        /// if not given. Helps with large maps that need a lot of
+        /// backtracking
+        #[arg(short, long)]
+        jobs: Option<usize>,
+    },
+}
+
+fn parse_size(s: &str) -> Result<(u16, u16), String> {
+    let (w, h) = s.split_once('x').ok_or("Expected WIDTHxHEIGHT")?;
+    let parse = |v: &str| v.parse::<u16>().map_err(|e| e.to_string());
+    Ok((parse(w)?, parse(h)?))
 }
 
 fn main() -> io::Result<()> {
     let args = Args::parse();
+    let mut config = Config::load(args.config.as_deref()).map_err(io::Error::other)?;
+    if args.theme.is_some() {
+        config.theme = args.theme;
+    }
+
+    if let Some(command) = args.command {
+        let theme = config.theme.unwrap_or_else(ThemeName::from_env);
+        return run_command(command, args.search, theme);
+    }
+
+    if args.script.is_some() || args.record.is_some() {
+        let board = match args.file {
+            Some(file) => app::load_board(&file).map_err(io::Error::other)?,
+            None => Default::default(),
+        };
+
+        let clock = Clock::default();
+        let out: Box<dyn io::Write> = match &args.record {
+            Some(path) => {
+                let file = io::BufWriter::new(File::create(path)?);
+                Box::new(Cast::new(file, args.size, clock.clone())?)
+            }
+            None => Box::new(io::sink()),
+        };
+
+        let mut headless = Headless::new(board, args.size, &config, args.search, out, clock)?;
+        let script = args.script.as_deref().unwrap_or("s<wait>");
+        headless.run_script(script).map_err(io::Error::other)?;
+        if args.record.is_none() {
+            print!("{}", headless.screen());
+        }
+        return Ok(());
+    }
 
     let mut app = if let Some(file) = args.file {
-        let data = std::fs::read_to_string(file).expect("Couldn't read file");
-        App::from_data(data)
+        App::from_file(&file, &config, args.search)
     } else {
-        App::new()
+        App::new(&config, args.search)
     };
     app.run()
 }
+
+fn run_command(command: Command, search: Search, theme: ThemeName) -> io::Result<()> {
+    match command {
+        Command::Svg {
+            file,
+            output,
+            solve,
+        } => {
+            let mut board = app::load_board(&file).map_err(io::Error::other)?;
+            if solve {
+                let mut wfc = search.wfc();
+                board = wfc
+                    .solve(board)
+                    .ok_or_else(|| io::Error::other(unsolved(&file, &wfc)))?;
+            }
+
+            let svg = export::svg::render(&board, export::svg::CELL_SIZE);
+            write_output(output, &svg)
+        }
+        Command::Trace { file, output } => {
+            let board = app::load_board(&file).map_err(io::Error::other)?;
+            let mut out: Box<dyn io::Write> = match output {
+                Some(path) => Box::new(io::BufWriter::new(File::create(path)?)),
+                None => Box::new(io::stdout().lock()),
+            };
+
+            let mut wfc = search.wfc();
+            let solved = export::trace::write(board, &mut wfc, &mut out)?;
+            out.flush()?;
+            if solved.is_none() {
+                eprintln!("{}", unsolved(&file, &wfc));
+            }
+            Ok(())
+        }
+        Command::Book {
+            file,
+            output,
+            per_page,
+            title,
+        } => {
+            let puzzles = read_collection(&file)?;
+
+            let html = export::book::render(&puzzles, per_page, &title, &mut search.wfc());
+            write_output(output, &html)
+        }
+        Command::Bench {
+            file,
+            slowest,
+            jobs,
+        } => {
+            let report = with_collection(&file, |puzzles| {
+                bench::run(puzzles, &search, batch::jobs(jobs), slowest)
+            })?;
+            print!("{}", report);
+            Ok(())
+        }
+        Command::Solve {
+            file,
+            output,
+            jobs,
+            split,
+            count,
+        } => {
+            let mut out: Box<dyn io::Write> = match output {
+                Some(path) => Box::new(io::BufWriter::new(File::create(path)?)),
+                None => Box::new(io::BufWriter::new(io::stdout().lock())),
+            };
+
+            let jobs = batch::jobs(jobs);
+            let mut res = Ok(());
+            let mut n = 0;
+            let mut write = |line: String, note: Option<String>| {
+                n += 1;
+                if let Some(note) = note {
+                    eprintln!("{}: puzzle {}: {}", file, n, note);
+                }
+                if res.is_ok() {
+                    res = writeln!(out, "{}", line);
+                }
+            };
+            let unsolved = |gave_up| if gave_up { "gave up" } else { "no solution" };
+
+            with_collection(&file, |puzzles| {
+                if count {
+                    for p in puzzles {
+                        let (solutions, gave_up) = parallel::count(p, &search, jobs);
+                        let note = gave_up.then(|| "gave up, the count is a lower bound".into());
+                        write(solutions.to_string(), note);
+                    }
+                } else if split {
+                    for p in puzzles {
+                        match parallel::solve(p, &search, jobs) {
+                            (Some(s), _) => write(s.to_line(), None),
+                            (None, gave_up) => write(p.to_line(), Some(unsolved(gave_up).into())),
+                        }
+                    }
+                } else {
+                    batch::solve(puzzles, &search, jobs, |s| match s.solution {
+                        Some(solution) => write(solution.to_line(), None),
+                        None => write(s.puzzle.to_line(), Some(unsolved(s.gave_up).into())),
+                    });
+                }
+            })?;
+            res?;
+            out.flush()
+        }
+        Command::Convert {
+            file,
+            output,
+            from,
+            to,
+        } => {
+            let data = std::fs::read_to_string(&file)?;
+            let from = from
+                .or_else(|| Format::from_path(&file))
+                .unwrap_or_else(|| Format::detect(&data));
+            let to = to
+                .or_else(|| output.as_deref().and_then(Format::from_path))
+                .unwrap_or(Format::Line);
+
+            let puzzles = from
+                .parse(&data)
+                .map_err(|e| io::Error::other(format!("{}: {}", file, e)))?;
+            write_output(output, &to.write_all(&puzzles))
+        }
+        Command::Generate {
+            sample,
+            output,
+            n,
+            width,
+            height,
+            rotate,
+            reflect,
+            jobs,
+        } => {
+            let data = std::fs::read_to_string(&sample)?;
+            let augment = Augment { rotate, reflect };
+            let model = Overlapping::new(&data, n, (width, height), augment)
+                .map_err(|e| io::Error::other(format!("{}: {}", sample, e)))?;
+
+            // the first cell with fewest options would give the same map
+            // every time
+            let search = Search {
+                cells: search.cells.or(Some(CellChoice::MrvRandom)),
+                ..search
+            };
+            let wave = model.wave();
+            let map = if let Some(jobs) = jobs {
+                match parallel::solve_model(model.clone(), wave, &search, jobs.max(1)) {
+                    (Some(w), _) => model.render(&w),
+                    (None, true) => {
+                        let e = format!("Gave up on {}", sample);
+                        return Err(io::Error::other(e));
+                    }
+                    (None, false) => {
+                        let e = format!("{} has no map of that size", sample);
+                        return Err(io::Error::other(e));
+                    }
+                }
+            } else {
+                let mut wfc = search.wfc_for(model);
+                match wfc.run(wave) {
+                    Some(w) => wfc.model().render(&w),
+                    None if wfc.gave_up() => {
+                        let e = format!("Gave up on {} after {} steps", sample, wfc.steps());
+                        return Err(io::Error::other(e));
+                    }
+                    None => {
+                        let e = format!("{} has no map of that size", sample);
+                        return Err(io::Error::other(e));
+                    }
+                }
+            };
+
+            let mut stdout = io::stdout().lock();
+            if output.is_none() && stdout.is_terminal() {
+                ui::print_map(&mut stdout, &Theme::named(theme), &map)
+            } else {
+                write_output(output, &(map.join("\n") + "\n"))
+            }
+        }
+    }
+}
+
+fn read_collection(file: &str) -> io::Result<Vec<board::Board>> {
+    let data = std::fs::read_to_string(file)?;
+    board::parse_collection(&data).map_err(|e| io::Error::other(format!("{}: {}", file, e)))
+}
+
+/// Hands the puzzles of `file` to `f` as they are read, stopping at the
+/// first one that can't be
+fn with_collection<T>(
+    file: &str,
+    f: impl FnOnce(&mut dyn Iterator<Item = board::Board>) -> T,
+) -> io::Result<T> {
+    let reader = io::BufReader::new(File::open(file)?);
+    let mut err = None;
+    let mut puzzles =
+        board::Collection::new(reader).map_while(|p| p.map_err(|e| err = Some(e)).ok());
+    let res = f(&mut puzzles);
+    drop(puzzles);
+
+    match err {
+        Some(e) => Err(io::Error::other(format!("{}: {}", file, e))),
+        None => Ok(res),
+    }
+}
+
+/// Why solving `file` failed
+fn unsolved(file: &str, wfc: &Wfc) -> String {
+    if wfc.gave_up() {
+        format!("Gave up on {} after {} steps", file, wfc.steps())
+    } else {
+        format!("{} has no solution", file)
+    }
+}
+
+/// Writes to the file at `path`, or stdout if there is none
+fn write_output(path: Option<String>, data: &str) -> io::Result<()> {
+    match path {
+        Some(path) => std::fs::write(path, data),
+        None => {
+            print!("{}", data);
+            Ok(())
+        }
+    }
+}
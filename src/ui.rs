@@ -1,134 +1,422 @@
-use crate::board::Board;
+use crate::board::{houses_of, Board, Cell};
 use crossterm::{
-    cursor, execute,
-    style::Stylize,
+    cursor, event, execute, queue,
+    style::{ContentStyle, PrintStyledContent, StyledContent, Stylize},
     terminal::{self, Clear, ClearType},
 };
+pub use frame::Frame;
+pub use status::Severity;
+use status::{Status, MAX_MESSAGES};
 use std::{
-    collections::HashMap,
-    io::{self, stdout},
+    io::{self, stdout, BufWriter, Write},
+    rc::Rc,
+    time::{Duration, Instant},
 };
+use theme::overlay;
+pub use theme::{Theme, ThemeName};
+
+mod frame;
+mod status;
+mod theme;
+
+/// Where the app reads the time since the session started: the wall clock,
+/// or the clock of a headless session, which only moves when it is moved
+#[derive(Clone)]
+pub enum Time {
+    Wall(Instant),
+    Session(Rc<std::cell::Cell<Duration>>),
+}
+
+impl Time {
+    pub fn now(&self) -> Duration {
+        match self {
+            Time::Wall(start) => start.elapsed(),
+            Time::Session(clock) => clock.get(),
+        }
+    }
+}
 
 pub struct Ui {
-    messages: HashMap<(u16, u16), fn()>,
+    status: Status,
+    time: Time,
+    help: Option<String>,
+    layout: Layout,
+    theme: Theme,
+    underline_conflicts: bool,
+
+    out: Box<dyn Write>,
+    // owns the terminal's raw mode and has to restore it
+    terminal: bool,
+    // what is currently on the terminal
+    screen: Frame,
+    clear: bool,
+    // board cell the cursor goes to on the next draw, hidden if none
+    cursor: Option<(usize, usize)>,
+    // whether the terminal shows the cursor, so it's only told on changes
+    cursor_shown: bool,
+}
+
+#[derive(PartialEq, Eq, Clone, Copy)]
+pub enum Layout {
+    /// 7x3 cells with room for the 3x3 grid of candidates
+    Large,
+    /// One character per cell, with borders only around squares
+    Compact,
 }
 
 const CELL_WIDTH: usize = 7;
 const CELL_HEIGHT: usize = 3;
 
+impl Layout {
+    /// Largest layout that fits the board with the status messages under
+    /// it. The help goes wherever there is room left
+    pub fn for_size(width: u16, height: u16) -> Self {
+        let large = Layout::Large;
+        // a blank line, then as many messages as can be shown at once
+        let below = 1 + MAX_MESSAGES;
+        if width as usize >= large.width() && height as usize >= large.height() + below {
+            large
+        } else {
+            Layout::Compact
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        match self {
+            Layout::Large => 9 * (CELL_WIDTH + 1) + 1,
+            Layout::Compact => 9 + 4,
+        }
+    }
+
+    pub fn height(&self) -> usize {
+        match self {
+            Layout::Large => 9 * (CELL_HEIGHT + 1) + 1,
+            Layout::Compact => 9 + 4,
+        }
+    }
+
+    /// Terminal position of the middle of a cell
+    fn cell_center(&self, (row, col): (usize, usize)) -> (usize, usize) {
+        match self {
+            Layout::Large => (
+                (CELL_WIDTH + 1) * col + CELL_WIDTH / 2 + 1,
+                (CELL_HEIGHT + 1) * row + CELL_HEIGHT / 2 + 1,
+            ),
+            Layout::Compact => (col + col / 3 + 1, row + row / 3 + 1),
+        }
+    }
+
+    /// Board cell under a terminal position, along with the candidate digit
+    /// whose slot in the cell's 3x3 mini-grid is under it
+    fn cell_at(&self, x: usize, y: usize) -> Option<((usize, usize), Option<u8>)> {
+        if x == 0 || y == 0 {
+            return None;
+        }
+
+        match self {
+            Layout::Large => {
+                let (col, dx) = ((x - 1) / (CELL_WIDTH + 1), (x - 1) % (CELL_WIDTH + 1));
+                let (row, dy) = ((y - 1) / (CELL_HEIGHT + 1), (y - 1) % (CELL_HEIGHT + 1));
+                if row > 8 || col > 8 || dx == CELL_WIDTH || dy == CELL_HEIGHT {
+                    return None;
+                }
+
+                let num = (dx % 2 == 1).then(|| (dy * 3 + dx / 2 + 1) as u8);
+                Some(((row, col), num))
+            }
+            Layout::Compact => {
+                let (col, dx) = ((x - 1) / 4 * 3, (x - 1) % 4);
+                let (row, dy) = ((y - 1) / 4 * 3, (y - 1) % 4);
+                if row > 8 || col > 8 || dx == 3 || dy == 3 {
+                    return None;
+                }
+
+                Some(((row + dy, col + dx), None))
+            }
+        }
+    }
+}
+
 impl Ui {
     pub fn new() -> io::Result<Self> {
         terminal::enable_raw_mode()?;
-        execute!(stdout(), Clear(ClearType::All), cursor::Hide)?;
+        execute!(
+            stdout(),
+            Clear(ClearType::All),
+            cursor::Hide,
+            event::EnableMouseCapture
+        )?;
+        let (width, height) = terminal::size()?;
 
-        Ok(Self {
-            messages: HashMap::default(),
-        })
+        let out = BufWriter::with_capacity(1 << 16, stdout());
+        let mut ui = Self::with_writer(Box::new(out), width, height);
+        ui.terminal = true;
+        Ok(ui)
     }
 
-    pub fn draw(&self, b: &Board) -> io::Result<()> {
-        draw_board(b)?;
+    /// Ui that renders into `out` as if it were a terminal of the given
+    /// size, without touching the real one
+    pub fn with_writer(out: Box<dyn Write>, width: u16, height: u16) -> Self {
+        Self {
+            status: Status::default(),
+            time: Time::Wall(Instant::now()),
+            help: None,
+            layout: Layout::for_size(width, height),
+            theme: Theme::default(),
+            underline_conflicts: false,
 
-        for ((x, y), f) in &self.messages {
-            execute!(stdout(), cursor::MoveTo(*x, *y))?;
-            f()
+            out,
+            terminal: false,
+            screen: Frame::new(width as usize, height as usize),
+            clear: false,
+            cursor: None,
+            cursor_shown: false,
         }
-
-        Ok(())
     }
 
-    pub fn set_cursor_onboard(&self, pos: Option<(usize, usize)>) -> io::Result<()> {
-        match pos {
-            Some((y, x)) => {
-                let x = (CELL_WIDTH + 1) * x + CELL_WIDTH / 2 + 1;
-                let y = (CELL_HEIGHT + 1) * y + CELL_HEIGHT / 2 + 1;
-                execute!(
-                    stdout(),
-                    cursor::MoveTo(x as u16, y as u16),
-                    cursor::SetCursorStyle::BlinkingUnderScore,
-                    cursor::Show,
-                )
+    /// Renders a frame off-screen and writes only the cells that changed
+    /// since the last one in a single flush
+    pub fn draw(&mut self, b: &Board, selected: Option<(usize, usize)>) -> io::Result<()> {
+        let (width, height) = self.screen.size();
+        let mut frame = Frame::new(width, height);
+        let (theme, underline) = (&self.theme, self.underline_conflicts);
+        match self.layout {
+            Layout::Large => draw_board(&mut frame, theme, b, underline, selected),
+            Layout::Compact => draw_board_compact(&mut frame, theme, b, underline, selected),
+        }
+
+        // status messages stack up under the board
+        self.status.expire(self.time.now());
+        let below = self.layout.height() + 1;
+        for (y, m) in (below..).zip(self.status.messages()) {
+            frame.move_to(0, y);
+            frame.put(StyledContent::new(self.theme.severity(m.severity), &m.text));
+        }
+        // the help goes beside the board if it fits there, or else under
+        // the messages, cut off at the bottom of the screen
+        if let Some(help) = &self.help {
+            let beside = self.layout.width() + 2;
+            let help_width = help.lines().map(|l| l.chars().count()).max();
+            let (x, y) = match help_width {
+                Some(w) if beside + w <= width => (beside, 0),
+                _ => (0, below + MAX_MESSAGES + 1),
+            };
+            for (y, line) in (y..).zip(help.lines()) {
+                frame.move_to(x, y);
+                frame.put(line.stylize());
+            }
+        }
+
+        if self.clear {
+            queue!(self.out, Clear(ClearType::All))?;
+            self.clear = false;
+        }
+        frame.diff(&self.screen, &mut self.out)?;
+        match self.cursor {
+            Some(pos) => {
+                let (x, y) = self.layout.cell_center(pos);
+                queue!(self.out, cursor::MoveTo(x as u16, y as u16))?;
+                if !self.cursor_shown {
+                    queue!(
+                        self.out,
+                        cursor::SetCursorStyle::BlinkingUnderScore,
+                        cursor::Show
+                    )?;
+                    self.cursor_shown = true;
+                }
             }
-            None => execute!(stdout(), cursor::Hide),
+            None if self.cursor_shown => {
+                queue!(self.out, cursor::Hide)?;
+                self.cursor_shown = false;
+            }
+            None => (),
         }
+        self.out.flush()?;
+
+        self.screen = frame;
+        Ok(())
+    }
+
+    /// What was drawn last
+    pub fn screen(&self) -> &Frame {
+        &self.screen
+    }
+
+    pub fn resize(&mut self, width: u16, height: u16) {
+        self.layout = Layout::for_size(width, height);
+        self.redraw(width as usize, height as usize);
+    }
+
+    /// Clears the screen before the next draw and forgets what was on it,
+    /// so that draw repaints everything
+    fn redraw(&mut self, width: usize, height: usize) {
+        self.screen = Frame::new(width, height);
+        self.clear = true;
+    }
+
+    /// Puts the cursor on a board cell from the next draw on, or hides it
+    pub fn set_cursor_onboard(&mut self, pos: Option<(usize, usize)>) {
+        self.cursor = pos;
+    }
+
+    /// Board cell under a terminal position, along with the candidate digit
+    /// under it when the layout shows candidates
+    pub fn cell_at(&self, x: u16, y: u16) -> Option<((usize, usize), Option<u8>)> {
+        self.layout.cell_at(x as usize, y as usize)
+    }
+
+    pub fn set_time(&mut self, time: Time) {
+        self.time = time;
+    }
+
+    /// Time since the session started, as messages and timings see it
+    pub fn now(&self) -> Duration {
+        self.time.now()
     }
 
-    pub fn has(&self, at: (u16, u16)) -> bool {
-        self.messages.contains_key(&at)
+    /// Shows a status message for as long as its severity calls for
+    pub fn notify(&mut self, severity: Severity, text: impl Into<String>) {
+        let now = self.time.now();
+        self.status
+            .push(now, severity, text.into(), Some(severity.timeout()));
     }
 
-    pub fn add_msg(&mut self, at: (u16, u16), f: fn()) {
-        self.messages.insert(at, f);
+    /// Shows a status message for `timeout`, or until pushed out by newer
+    /// ones if there is none
+    pub fn notify_for(
+        &mut self,
+        severity: Severity,
+        text: impl Into<String>,
+        timeout: Option<Duration>,
+    ) {
+        self.status
+            .push(self.time.now(), severity, text.into(), timeout);
     }
 
-    pub fn remove_msg(&mut self, at: (u16, u16)) {
-        self.messages.remove(&at);
+    pub fn has_help(&self) -> bool {
+        self.help.is_some()
+    }
+
+    pub fn set_help(&mut self, help: Option<String>) {
+        self.help = help;
+    }
+
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.theme = theme;
+    }
+
+    pub fn toggle_underline_conflicts(&mut self) {
+        self.underline_conflicts = !self.underline_conflicts;
     }
 }
 
 impl Drop for Ui {
     fn drop(&mut self) {
+        if !self.terminal {
+            return;
+        }
+
         execute!(
-            stdout(),
+            self.out,
             cursor::MoveTo(0, 0),
             cursor::SetCursorStyle::DefaultUserShape,
-            cursor::Show
+            cursor::Show,
+            event::DisableMouseCapture
         )
         .unwrap();
         terminal::disable_raw_mode().unwrap();
     }
 }
 
-pub fn draw_board(b: &Board) -> io::Result<()> {
-    execute!(stdout(), Clear(ClearType::All), cursor::MoveTo(0, 0))?;
+/// Prints the lines of a generated map, giving each distinct character its
+/// own style from the theme
+pub fn print_map(out: &mut impl Write, theme: &Theme, rows: &[String]) -> io::Result<()> {
+    let mut seen = vec![];
+    for row in rows {
+        for ch in row.chars() {
+            let i = match seen.iter().position(|&c| c == ch) {
+                Some(i) => i,
+                None => {
+                    seen.push(ch);
+                    seen.len() - 1
+                }
+            };
+            queue!(
+                out,
+                PrintStyledContent(StyledContent::new(theme.symbol(i), ch))
+            )?;
+        }
+        writeln!(out)?;
+    }
+    out.flush()
+}
 
+pub fn draw_board(
+    f: &mut Frame,
+    theme: &Theme,
+    b: &Board,
+    underline_conflicts: bool,
+    selected: Option<(usize, usize)>,
+) {
     let bold_vertical = |i| (i + 1) % 3 == 0;
-    horiz_border(BorderKind::Down, 9, CELL_WIDTH, bold_vertical, true);
+    horiz_border(
+        f,
+        theme,
+        BorderKind::Down,
+        9,
+        CELL_WIDTH,
+        bold_vertical,
+        true,
+    );
     for i in 0..9 {
         for j in 0..CELL_HEIGHT {
-            print!("{}", "║".grey().dim());
+            f.put(StyledContent::new(theme.border, "║"));
 
             for k in 0..9 {
-                let c = &b[(i, k)];
-                let sep = if bold_vertical(k) {
-                    "║".grey().dim()
-                } else {
-                    "│".grey().dim()
+                let idx = i * 9 + k;
+                let sep =
+                    StyledContent::new(theme.border, if bold_vertical(k) { "║" } else { "│" });
+
+                let paint = |s: StyledContent<String>| match highlight(theme, b, selected, idx) {
+                    Some(style) => overlay(s, style),
+                    None => s,
                 };
+                let mid = j == (CELL_HEIGHT / 2);
 
-                if c.is_maybe() {
+                let content = if let Some(m) = marks(b, idx) {
                     let num = |n: usize| {
-                        if c.maybe_get_num(n as u8) {
+                        if m & 1 << n != 0 {
                             n.to_string()
                         } else {
                             " ".into()
                         }
                     };
 
-                    print!(
-                        " {} {} {} {}",
-                        num(j * 3 + 1),
-                        num(j * 3 + 2),
-                        num(j * 3 + 3),
-                        sep
-                    )
+                    f.put(paint(StyledContent::new(
+                        theme.candidate,
+                        format!(" {} {} {} ", num(j * 3 + 1), num(j * 3 + 2), num(j * 3 + 3)),
+                    )));
+                    f.put(sep);
+                    continue;
+                } else if mid {
+                    cell_content(theme, b, idx, underline_conflicts)
                 } else {
-                    print!(
-                        "   {}   {}",
-                        if j == (CELL_HEIGHT / 2) {
-                            b[(i, k)].to_string()
-                        } else {
-                            " ".into()
-                        },
-                        sep,
-                    );
-                }
+                    " ".to_string().stylize()
+                };
+
+                let pad = || paint("   ".to_string().stylize());
+                f.put(pad());
+                f.put(paint(content));
+                f.put(pad());
+                f.put(sep);
             }
-            print!("\r\n");
+            f.put("\r\n".stylize());
         }
 
         if i != 8 {
             horiz_border(
+                f,
+                theme,
                 BorderKind::Both,
                 9,
                 CELL_WIDTH,
@@ -138,9 +426,95 @@ pub fn draw_board(b: &Board) -> io::Result<()> {
         }
     }
 
-    horiz_border(BorderKind::Up, 9, CELL_WIDTH, bold_vertical, true);
+    horiz_border(f, theme, BorderKind::Up, 9, CELL_WIDTH, bold_vertical, true);
+}
+
+pub fn draw_board_compact(
+    f: &mut Frame,
+    theme: &Theme,
+    b: &Board,
+    underline_conflicts: bool,
+    selected: Option<(usize, usize)>,
+) {
+    let bold_vertical = |_| true;
+    horiz_border(f, theme, BorderKind::Down, 3, 3, bold_vertical, true);
+    for i in 0..9 {
+        f.put(StyledContent::new(theme.border, "║"));
+        for k in 0..9 {
+            let idx = i * 9 + k;
+            let content = if marks(b, idx).is_some() {
+                StyledContent::new(theme.border, "·".to_string())
+            } else {
+                cell_content(theme, b, idx, underline_conflicts)
+            };
+
+            match highlight(theme, b, selected, idx) {
+                Some(style) => f.put(overlay(content, style)),
+                None => f.put(content),
+            }
+            if (k + 1) % 3 == 0 {
+                f.put(StyledContent::new(theme.border, "║"));
+            }
+        }
+        f.put("\r\n".stylize());
+
+        if i != 8 && (i + 1) % 3 == 0 {
+            horiz_border(f, theme, BorderKind::Both, 3, 3, bold_vertical, true);
+        }
+    }
+    horiz_border(f, theme, BorderKind::Up, 3, 3, bold_vertical, true);
+}
+
+/// Candidates of a cell, or the user's notes on an empty one, to show in
+/// place of a value
+fn marks(b: &Board, idx: usize) -> Option<u16> {
+    if b.is_dead(idx) {
+        return None;
+    }
+    match b[idx] {
+        Cell::Maybe(m) => Some(m),
+        Cell::Empty if b.notes(idx) != 0 => Some(b.notes(idx)),
+        _ => None,
+    }
+}
+
+/// Single character shown for a filled, empty or dead cell
+fn cell_content(
+    theme: &Theme,
+    b: &Board,
+    idx: usize,
+    underline_conflicts: bool,
+) -> StyledContent<String> {
+    let content = if b.is_dead(idx) {
+        StyledContent::new(theme.dead, "×".to_string())
+    } else {
+        theme.cell(&b[idx])
+    };
+
+    if underline_conflicts && b.in_conflicting_house(idx) {
+        content.underlined()
+    } else {
+        content
+    }
+}
+
+/// Overlay for a cell sharing the selected cell's digit, or one of its peers
+fn highlight<'a>(
+    theme: &'a Theme,
+    b: &Board,
+    selected: Option<(usize, usize)>,
+    idx: usize,
+) -> Option<&'a ContentStyle> {
+    let (row, col) = selected?;
+    let sel = row * 9 + col;
 
-    Ok(())
+    if b[sel].value().is_some() && b[sel].value() == b[idx].value() {
+        Some(&theme.same_digit)
+    } else if houses_of(sel).iter().any(|h| houses_of(idx).contains(h)) {
+        Some(&theme.peer)
+    } else {
+        None
+    }
 }
 
 enum BorderKind {
@@ -203,6 +577,8 @@ impl BorderKind {
 }
 
 fn horiz_border(
+    f: &mut Frame,
+    theme: &Theme,
     kind: BorderKind,
     cells: usize,
     cell_size: usize,
@@ -211,12 +587,144 @@ fn horiz_border(
 ) {
     let mid = if bold { "═" } else { "─" };
 
-    print!("{}", kind.left_corner(bold, true).grey().dim());
+    let style = |s| StyledContent::new(theme.border, s);
+    f.put(style(kind.left_corner(bold, true)));
     for i in 0..cells {
-        print!("{}", mid.repeat(cell_size).grey().dim());
+        f.put(StyledContent::new(theme.border, mid.repeat(cell_size)));
         if i != cells - 1 {
-            print!("{}", kind.middle(bold, bold_vertical(i)).grey().dim());
+            f.put(style(kind.middle(bold, bold_vertical(i))));
         }
     }
-    print!("{}\r\n", kind.right_corner(bold, true).grey().dim());
+    f.put(style(kind.right_corner(bold, true)));
+    f.put("\r\n".stylize());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        app::headless::{Clock, Headless},
+        config::Config,
+        wfc::Search,
+    };
+
+    const LARGE: &str = "\
+╔═══════╤═══════╤═══════╦═══════╤═══════╤═══════╦═══════╤═══════╤═══════╗
+║       │       │ 1 2   ║       │       │       ║       │       │       ║
+║   5   │   3   │       ║       │   7   │       ║       │       │       ║
+║       │       │     9 ║       │       │       ║       │       │       ║
+╟───────┼───────┼───────╫───────┼───────┼───────╫───────┼───────┼───────╢
+║       │       │       ║       │       │       ║       │       │       ║
+║   6   │       │       ║   1   │   9   │   5   ║       │       │       ║
+║       │       │       ║       │       │       ║       │       │       ║
+╟───────┼───────┼───────╫───────┼───────┼───────╫───────┼───────┼───────╢
+║       │       │       ║       │       │       ║       │       │       ║
+║       │   9   │   8   ║       │       │       ║       │   6   │       ║
+║       │       │       ║       │       │       ║       │       │       ║
+╠═══════╪═══════╪═══════╬═══════╪═══════╪═══════╬═══════╪═══════╪═══════╣
+║       │       │       ║       │       │       ║       │       │       ║
+║   8   │       │       ║       │   6   │       ║       │       │   3   ║
+║       │       │       ║       │       │       ║       │       │       ║
+╟───────┼───────┼───────╫───────┼───────┼───────╫───────┼───────┼───────╢
+║       │       │       ║       │       │       ║       │       │       ║
+║   4   │       │       ║   8   │       │   3   ║       │       │   1   ║
+║       │       │       ║       │       │       ║       │       │       ║
+╟───────┼───────┼───────╫───────┼───────┼───────╫───────┼───────┼───────╢
+║       │       │       ║       │       │       ║       │       │       ║
+║   7   │       │       ║       │   2   │       ║       │       │   6   ║
+║       │       │       ║       │       │       ║       │       │       ║
+╠═══════╪═══════╪═══════╬═══════╪═══════╪═══════╬═══════╪═══════╪═══════╣
+║       │       │       ║       │       │       ║       │       │       ║
+║       │   6   │       ║       │       │       ║   2   │   8   │       ║
+║       │       │       ║       │       │       ║       │       │       ║
+╟───────┼───────┼───────╫───────┼───────┼───────╫───────┼───────┼───────╢
+║       │       │       ║       │       │       ║       │       │       ║
+║       │       │       ║   4   │   1   │   9   ║       │       │   5   ║
+║       │       │       ║       │       │       ║       │       │       ║
+╟───────┼───────┼───────╫───────┼───────┼───────╫───────┼───────┼───────╢
+║       │       │       ║       │       │       ║       │       │       ║
+║       │       │       ║       │   8   │       ║       │   7   │   9   ║
+║       │       │       ║       │       │       ║       │       │       ║
+╚═══════╧═══════╧═══════╩═══════╧═══════╧═══════╩═══════╧═══════╧═══════╝
+";
+
+    const COMPACT: &str = "\
+╔═══╦═══╦═══╗
+║53·║ 7 ║   ║
+║6  ║195║   ║
+║ 98║   ║ 6 ║
+╠═══╬═══╬═══╣
+║8  ║ 6 ║  3║
+║4  ║8 3║  1║
+║7  ║ 2 ║  6║
+╠═══╬═══╬═══╣
+║ 6 ║   ║28 ║
+║   ║419║  5║
+║   ║ 8 ║ 79║
+╚═══╩═══╩═══╝
+";
+
+    const SOLVED: &str = "\
+╔═══╦═══╦═══╗
+║534║678║912║
+║672║195║348║
+║198║342║567║
+╠═══╬═══╬═══╣
+║859║761║423║
+║426║853║791║
+║713║924║856║
+╠═══╬═══╬═══╣
+║961║537║284║
+║287║419║635║
+║345║286║179║
+╚═══╩═══╩═══╝
+
+Solved in 2.6s after 52 steps";
+
+    /// The first example, with notes on the empty space next to its 3
+    fn board() -> Board {
+        let mut b: Board = std::fs::read_to_string("example/1.txt")
+            .unwrap()
+            .parse()
+            .unwrap();
+        for n in [1, 2, 9] {
+            b.toggle_note((0, 2), n);
+        }
+        b
+    }
+
+    fn render(layout: Layout) -> String {
+        let mut f = Frame::new(layout.width(), layout.height());
+        let theme = Theme::default();
+        match layout {
+            Layout::Large => draw_board(&mut f, &theme, &board(), false, Some((4, 4))),
+            Layout::Compact => draw_board_compact(&mut f, &theme, &board(), false, Some((4, 4))),
+        }
+        f.to_string()
+    }
+
+    #[test]
+    fn draws_board() {
+        assert_eq!(render(Layout::Large), LARGE);
+    }
+
+    #[test]
+    fn draws_board_compact() {
+        assert_eq!(render(Layout::Compact), COMPACT);
+    }
+
+    #[test]
+    fn headless_solve() {
+        let b = crate::app::load_board("example/1.txt").unwrap();
+        let out = Box::new(io::sink());
+        let clock = Clock::default();
+        let config = Config::default();
+        let mut headless =
+            Headless::new(b, (40, 30), &config, Search::default(), out, clock).unwrap();
+        headless.run_script("s<wait>").unwrap();
+
+        let screen = headless.screen().to_string();
+        let top = screen.lines().take(15).collect::<Vec<_>>().join("\n");
+        assert_eq!(top, SOLVED);
+    }
 }
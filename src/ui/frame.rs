@@ -0,0 +1,123 @@
+use crossterm::{
+    cursor, queue,
+    style::{ContentStyle, PrintStyledContent, StyledContent},
+};
+use std::{
+    fmt::{self, Display},
+    io,
+};
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct Glyph {
+    pub ch: char,
+    pub style: ContentStyle,
+}
+
+impl Default for Glyph {
+    fn default() -> Self {
+        Self {
+            ch: ' ',
+            style: ContentStyle::default(),
+        }
+    }
+}
+
+/// Off-screen copy of the terminal that drawing code writes into
+#[derive(Clone, PartialEq)]
+pub struct Frame {
+    width: usize,
+    height: usize,
+    glyphs: Vec<Glyph>,
+    // where the next `put` writes, like a terminal cursor
+    pen: (usize, usize),
+}
+
+impl Frame {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            glyphs: vec![Glyph::default(); width * height],
+            pen: (0, 0),
+        }
+    }
+
+    pub fn move_to(&mut self, x: usize, y: usize) {
+        self.pen = (x, y);
+    }
+
+    /// Writes content at the pen, clipping anything outside the frame.
+    /// `\r` and `\n` move the pen the same way they move a raw mode cursor
+    pub fn put<D: Display>(&mut self, content: StyledContent<D>) {
+        for ch in content.content().to_string().chars() {
+            match ch {
+                '\r' => self.pen.0 = 0,
+                '\n' => self.pen.1 += 1,
+                _ => {
+                    let (x, y) = self.pen;
+                    if x < self.width && y < self.height {
+                        self.glyphs[y * self.width + x] = Glyph {
+                            ch,
+                            style: *content.style(),
+                        };
+                    }
+                    self.pen.0 += 1;
+                }
+            }
+        }
+    }
+
+    /// Queues the commands that turn `prev` into this frame on screen.
+    /// Both frames must have the same size
+    pub fn diff(&self, prev: &Frame, w: &mut impl io::Write) -> io::Result<()> {
+        for y in 0..self.height {
+            let row = y * self.width;
+            let mut x = 0;
+            while x < self.width {
+                if self.glyphs[row + x] == prev.glyphs[row + x] {
+                    x += 1;
+                    continue;
+                }
+
+                // batch the changed glyphs that share a style into one write
+                let style = self.glyphs[row + x].style;
+                let start = x;
+                let mut run = String::new();
+                while x < self.width
+                    && self.glyphs[row + x] != prev.glyphs[row + x]
+                    && self.glyphs[row + x].style == style
+                {
+                    run.push(self.glyphs[row + x].ch);
+                    x += 1;
+                }
+
+                queue!(
+                    w,
+                    cursor::MoveTo(start as u16, y as u16),
+                    PrintStyledContent(StyledContent::new(style, run))
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+}
+
+/// Plain text of the frame, without styles or trailing spaces
+impl Display for Frame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.width == 0 {
+            return Ok(());
+        }
+
+        for row in self.glyphs.chunks(self.width) {
+            let line = row.iter().map(|g| g.ch).collect::<String>();
+            writeln!(f, "{}", line.trim_end())?;
+        }
+        Ok(())
+    }
+}
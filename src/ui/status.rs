@@ -0,0 +1,66 @@
+use std::time::Duration;
+
+/// Most messages shown at once, older ones are dropped first
+pub const MAX_MESSAGES: usize = 5;
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Severity {
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    /// How long a message stays up unless told otherwise
+    pub fn timeout(&self) -> Duration {
+        match self {
+            Severity::Info => Duration::from_secs(3),
+            Severity::Success | Severity::Warning => Duration::from_secs(5),
+            Severity::Error => Duration::from_secs(8),
+        }
+    }
+}
+
+pub struct Message {
+    pub text: String,
+    pub severity: Severity,
+    // session time it goes away at
+    expires: Option<Duration>,
+}
+
+/// Stack of messages shown under the board, newest last
+#[derive(Default)]
+pub struct Status {
+    messages: Vec<Message>,
+}
+
+impl Status {
+    /// Adds a message at session time `now` that is removed after
+    /// `timeout`, or stays up until pushed out by newer ones if there is none
+    pub fn push(
+        &mut self,
+        now: Duration,
+        severity: Severity,
+        text: String,
+        timeout: Option<Duration>,
+    ) {
+        if self.messages.len() == MAX_MESSAGES {
+            self.messages.remove(0);
+        }
+
+        self.messages.push(Message {
+            text,
+            severity,
+            expires: timeout.map(|t| now + t),
+        });
+    }
+
+    pub fn expire(&mut self, now: Duration) {
+        self.messages.retain(|m| m.expires.is_none_or(|e| e > now));
+    }
+
+    pub fn messages(&self) -> &[Message] {
+        &self.messages
+    }
+}
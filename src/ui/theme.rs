@@ -0,0 +1,235 @@
+use crate::board::Cell;
+use crossterm::style::{Attribute, Attributes, Color, ContentStyle, StyledContent};
+use serde::Deserialize;
+
+use super::Severity;
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum ThemeName {
+    Default,
+    HighContrast,
+    /// Okabe-Ito palette, which stays distinct with red-green color blindness
+    Colorblind,
+    /// Only bold, italic, reversed and underlined text
+    Mono,
+}
+
+impl ThemeName {
+    /// Mono if the NO_COLOR convention asks for it, the default theme otherwise
+    pub fn from_env() -> Self {
+        match std::env::var_os("NO_COLOR") {
+            Some(v) if !v.is_empty() => ThemeName::Mono,
+            _ => ThemeName::Default,
+        }
+    }
+}
+
+pub struct Theme {
+    pub given: ContentStyle,
+    pub conflict: ContentStyle,
+    pub collapsed: ContentStyle,
+    pub candidate: ContentStyle,
+    pub dead: ContentStyle,
+    pub border: ContentStyle,
+    /// Laid over cells sharing the selected cell's digit
+    pub same_digit: ContentStyle,
+    /// Laid over the selected cell's row, column and square
+    pub peer: ContentStyle,
+
+    pub info: ContentStyle,
+    pub success: ContentStyle,
+    pub warning: ContentStyle,
+    pub error: ContentStyle,
+
+    /// Cycled through for the characters of a generated map
+    pub symbols: Vec<ContentStyle>,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::named(ThemeName::Default)
+    }
+}
+
+fn style(fg: Option<Color>, bg: Option<Color>, attrs: &[Attribute]) -> ContentStyle {
+    ContentStyle {
+        foreground_color: fg,
+        background_color: bg,
+        underline_color: None,
+        attributes: attrs.iter().fold(Attributes::default(), |acc, &a| acc | a),
+    }
+}
+
+impl Theme {
+    pub fn named(name: ThemeName) -> Self {
+        use Attribute::{Bold, Dim, Italic, Reverse, Underlined};
+
+        let plain = style(None, None, &[]);
+        match name {
+            ThemeName::Default => Self {
+                given: style(Some(Color::Blue), None, &[Bold]),
+                conflict: style(Some(Color::Red), None, &[Bold]),
+                collapsed: style(Some(Color::Green), None, &[Bold]),
+                candidate: plain,
+                dead: style(Some(Color::Red), None, &[Bold]),
+                border: style(Some(Color::Grey), None, &[Dim]),
+                same_digit: style(None, Some(Color::AnsiValue(58)), &[]),
+                peer: style(None, Some(Color::AnsiValue(236)), &[]),
+
+                info: plain,
+                success: style(Some(Color::Green), None, &[]),
+                warning: style(Some(Color::Yellow), None, &[]),
+                error: style(Some(Color::Red), None, &[Bold]),
+
+                symbols: [
+                    Color::Blue,
+                    Color::Green,
+                    Color::Yellow,
+                    Color::Magenta,
+                    Color::Cyan,
+                    Color::Red,
+                    Color::DarkGreen,
+                    Color::DarkYellow,
+                ]
+                .map(|c| style(Some(c), None, &[]))
+                .to_vec(),
+            },
+            ThemeName::HighContrast => Self {
+                given: style(Some(Color::White), None, &[Bold]),
+                conflict: style(Some(Color::White), Some(Color::DarkRed), &[Bold]),
+                collapsed: style(Some(Color::Cyan), None, &[Bold]),
+                candidate: style(Some(Color::White), None, &[]),
+                dead: style(Some(Color::White), Some(Color::DarkRed), &[Bold]),
+                border: style(Some(Color::White), None, &[]),
+                same_digit: style(None, None, &[Reverse]),
+                peer: style(None, Some(Color::AnsiValue(238)), &[]),
+
+                info: style(Some(Color::White), None, &[]),
+                success: style(Some(Color::Cyan), None, &[Bold]),
+                warning: style(Some(Color::Yellow), None, &[Bold]),
+                error: style(Some(Color::White), Some(Color::DarkRed), &[Bold]),
+
+                symbols: [Color::White, Color::Cyan, Color::Yellow, Color::Magenta]
+                    .map(|c| style(Some(c), None, &[Bold]))
+                    .to_vec(),
+            },
+            ThemeName::Colorblind => {
+                let orange = Color::Rgb {
+                    r: 230,
+                    g: 159,
+                    b: 0,
+                };
+                let sky_blue = Color::Rgb {
+                    r: 86,
+                    g: 180,
+                    b: 233,
+                };
+                let yellow = Color::Rgb {
+                    r: 240,
+                    g: 228,
+                    b: 66,
+                };
+
+                Self {
+                    given: style(None, None, &[Bold]),
+                    // underlined as well so it doesn't rely on hue alone
+                    conflict: style(Some(orange), None, &[Bold, Underlined]),
+                    collapsed: style(Some(sky_blue), None, &[Bold]),
+                    candidate: plain,
+                    dead: style(Some(orange), None, &[Bold]),
+                    border: style(Some(Color::Grey), None, &[Dim]),
+                    same_digit: style(None, None, &[Reverse]),
+                    peer: style(None, Some(Color::AnsiValue(236)), &[]),
+
+                    info: plain,
+                    success: style(Some(sky_blue), None, &[]),
+                    warning: style(Some(yellow), None, &[]),
+                    error: style(Some(orange), None, &[Bold]),
+
+                    symbols: [
+                        orange,
+                        sky_blue,
+                        yellow,
+                        Color::Rgb {
+                            r: 0,
+                            g: 158,
+                            b: 115,
+                        },
+                        Color::Rgb {
+                            r: 0,
+                            g: 114,
+                            b: 178,
+                        },
+                        Color::Rgb {
+                            r: 213,
+                            g: 94,
+                            b: 0,
+                        },
+                        Color::Rgb {
+                            r: 204,
+                            g: 121,
+                            b: 167,
+                        },
+                    ]
+                    .map(|c| style(Some(c), None, &[]))
+                    .to_vec(),
+                }
+            }
+            ThemeName::Mono => Self {
+                given: style(None, None, &[Bold]),
+                conflict: style(None, None, &[Bold, Reverse]),
+                collapsed: style(None, None, &[Italic]),
+                candidate: plain,
+                dead: style(None, None, &[Bold, Reverse]),
+                border: style(None, None, &[Dim]),
+                same_digit: style(None, None, &[Underlined]),
+                peer: plain,
+
+                info: plain,
+                success: style(None, None, &[Bold]),
+                warning: style(None, None, &[Bold]),
+                error: style(None, None, &[Bold, Reverse]),
+
+                symbols: vec![plain, style(None, None, &[Bold]), style(None, None, &[Dim])],
+            },
+        }
+    }
+
+    /// Digit of a filled cell, or a blank for one without a value. Boards
+    /// draw candidates themselves, so this is only for those without any
+    pub fn cell(&self, c: &Cell) -> StyledContent<String> {
+        match c {
+            Cell::Good(v) => StyledContent::new(self.given, v.to_string()),
+            Cell::Bad(v) => StyledContent::new(self.conflict, v.to_string()),
+            Cell::Collapsed(v) => StyledContent::new(self.collapsed, v.to_string()),
+            Cell::Empty | Cell::Maybe(_) => {
+                StyledContent::new(ContentStyle::default(), " ".to_string())
+            }
+        }
+    }
+
+    /// Style for the `i`th distinct character of a map
+    pub fn symbol(&self, i: usize) -> ContentStyle {
+        self.symbols[i % self.symbols.len()]
+    }
+
+    pub fn severity(&self, s: Severity) -> ContentStyle {
+        match s {
+            Severity::Info => self.info,
+            Severity::Success => self.success,
+            Severity::Warning => self.warning,
+            Severity::Error => self.error,
+        }
+    }
+}
+
+/// Adds the background and attributes of `overlay` to some content
+pub fn overlay(content: StyledContent<String>, overlay: &ContentStyle) -> StyledContent<String> {
+    let mut style = *content.style();
+    if overlay.background_color.is_some() {
+        style.background_color = overlay.background_color;
+    }
+    style.attributes.extend(overlay.attributes);
+    StyledContent::new(style, content.content().clone())
+}
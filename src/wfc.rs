@@ -1,54 +1,403 @@
 use crate::board::Board;
-use rand::{seq::SliceRandom, thread_rng};
+use serde::Serialize;
+use std::time::Instant;
 
-#[derive(Default)]
-pub struct Wfc {
-    stack: Vec<Board>,
+use backjump::Conflicts;
+pub use budget::{Budget, Restart};
+pub use heuristic::{CellChoice, CellHeuristic};
+use model::{values, View};
+pub use model::{Grid, Mask, Model, Sudoku};
+pub use overlapping::{Augment, Overlapping};
+pub use tiled::{Dir, TileWave, Tiled};
+pub use value::{ValueChoice, ValueOrder};
+
+mod backjump;
+pub mod budget;
+pub mod heuristic;
+pub mod model;
+pub mod overlapping;
+pub mod parallel;
+pub mod tiled;
+pub mod value;
+
+/// Search settings shared by everything that solves
+#[derive(clap::Args, Clone, Copy, Debug)]
+pub struct Search {
+    /// How the solver picks the next space to fill. Defaults to mrv, or
+    /// mrv-random when generating so each run is different
+    #[arg(long, global = true, value_enum)]
+    pub cells: Option<CellChoice>,
+
+    /// Which value the solver tries first in a space
+    #[arg(long, global = true, value_enum, default_value_t)]
+    pub values: ValueChoice,
+
+    #[command(flatten)]
+    pub budget: Budget,
+
+    /// Start the search over after a growing number of backtracks
+    #[arg(long, global = true, value_enum, default_value_t)]
+    pub restart: Restart,
+
+    /// Backtracks before the first restart
+    #[arg(long, global = true, default_value_t = 100)]
+    pub restart_base: u64,
+
+    /// Undo only the latest decision at a dead end, instead of jumping back
+    /// to the latest one that caused it
+    #[arg(long, global = true)]
+    pub chronological: bool,
+
+    /// How likely each digit is to be tried, as nine comma separated
+    /// weights for 1 to 9. Used by the entropy cell choice and the random
+    /// value choice
+    #[arg(long, global = true, value_parser = parse_weights)]
+    pub weights: Option<[f64; 9]>,
+}
+
+fn parse_weights(s: &str) -> Result<[f64; 9], String> {
+    let weights = s
+        .split(',')
+        .map(|w| match w.trim().parse::<f64>() {
+            Ok(w) if w >= 0.0 && w.is_finite() => Ok(w),
+            Ok(_) => Err(format!("{} isn't a weight of 0 or more", w)),
+            Err(e) => Err(format!("{}: {}", w, e)),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    weights
+        .try_into()
+        .map_err(|w: Vec<f64>| format!("Expected 9 weights, got {}", w.len()))
+}
+
+impl Default for Search {
+    fn default() -> Self {
+        Self {
+            cells: Default::default(),
+            values: Default::default(),
+            budget: Default::default(),
+            restart: Default::default(),
+            restart_base: 100,
+            chronological: false,
+            weights: None,
+        }
+    }
+}
+
+impl Search {
+    pub fn wfc(&self) -> Wfc {
+        self.wfc_for(self.sudoku())
+    }
+
+    /// Sudoku with the digit weights given
+    pub fn sudoku(&self) -> Sudoku {
+        self.weights.map(Sudoku::weighted).unwrap_or_default()
+    }
+
+    /// Search for some other model than Sudoku
+    pub fn wfc_for<M: Model>(&self, model: M) -> Wfc<M> {
+        Wfc::new(
+            model,
+            self.cells.unwrap_or_default().heuristic(),
+            self.values.order(),
+        )
+        .with_budget(self.budget)
+        .with_restarts(self.restart, self.restart_base)
+        .with_backjumping(!self.chronological)
+    }
 }
 
-pub enum StepResult {
-    Complete(Board),
-    InProgress(Board),
+/// Backtracking search over the cells of a model: collapse the cell the
+/// heuristic picks to a value, let the model narrow down the rest, and undo
+/// when some cell is left without options
+pub struct Wfc<M: Model = Sudoku> {
+    model: M,
+    stack: Vec<M::Wave>,
+    last_step: Option<Step>,
+    cells: Box<dyn CellHeuristic>,
+    values: Box<dyn ValueOrder>,
+
+    budget: Budget,
+    restart: Restart,
+    restart_base: u64,
+    backjump: bool,
+    conflicts: Conflicts,
+
+    // wave the search restarts from
+    start: Option<M::Wave>,
+    started: Instant,
+    steps: u64,
+    backtracks: u64,
+    restarts: u32,
+    // backtracks since the last restart
+    run_backtracks: u64,
+}
+
+impl Default for Wfc {
+    fn default() -> Self {
+        Search::default().wfc()
+    }
+}
+
+/// What a call to `Wfc::step` did
+#[derive(Serialize, Clone, Debug)]
+pub struct Step {
+    /// Cell that was picked, absent once the board is complete
+    pub cell: Option<usize>,
+    pub entropy: u16,
+    /// Value tried, absent when the cell ran out of values
+    pub value: Option<u8>,
+    /// Values of the cell left to try if this one fails
+    pub remaining: Vec<u8>,
+    /// Boards on the stack afterwards
+    pub depth: usize,
+    pub action: StepAction,
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum StepAction {
+    Push,
+    Pop,
+    Complete,
+    Restart,
+    GaveUp,
+}
+
+impl Step {
+    /// Step that didn't pick a cell
+    fn without_cell(action: StepAction, depth: usize) -> Self {
+        Self {
+            cell: None,
+            entropy: 0,
+            value: None,
+            remaining: vec![],
+            depth,
+            action,
+        }
+    }
+}
+
+pub enum StepResult<W = Board> {
+    Complete(W),
+    InProgress(W),
     Impossible,
+    /// The search ran out of budget
+    GaveUp,
 }
 
-impl Wfc {
-    pub fn init(&mut self, b: Board) {
-        self.stack = vec![b];
+impl<M: Model> Wfc<M> {
+    pub fn new(model: M, cells: Box<dyn CellHeuristic>, values: Box<dyn ValueOrder>) -> Self {
+        Self {
+            model,
+            stack: vec![],
+            last_step: None,
+            cells,
+            values,
+
+            budget: Budget::default(),
+            restart: Restart::Never,
+            restart_base: 0,
+            backjump: true,
+            conflicts: Conflicts::default(),
+
+            start: None,
+            started: Instant::now(),
+            steps: 0,
+            backtracks: 0,
+            restarts: 0,
+            run_backtracks: 0,
+        }
     }
 
-    pub fn step(&mut self) -> StepResult {
-        let b = self.stack.last().unwrap().clone();
-        let mut posibilities = b
-            .get_uncollapsed()
-            .into_iter()
-            .map(|(i, c)| (i, c.entropy()))
-            .collect::<Vec<_>>();
-        if posibilities.is_empty() {
-            return StepResult::Complete(b);
+    pub fn with_budget(mut self, budget: Budget) -> Self {
+        self.budget = budget;
+        self
+    }
+
+    /// Restarts after `base` backtracks scaled by the policy
+    pub fn with_restarts(mut self, restart: Restart, base: u64) -> Self {
+        self.restart = restart;
+        self.restart_base = base.max(1);
+        self
+    }
+
+    /// Whether a dead end undoes every decision up to the latest one that
+    /// ruled out its candidates, rather than only the latest decision
+    pub fn with_backjumping(mut self, backjump: bool) -> Self {
+        self.backjump = backjump;
+        self
+    }
+
+    pub fn init(&mut self, w: M::Wave) {
+        self.reset();
+        self.stack = vec![w.clone()];
+        self.start = Some(w);
+    }
+
+    /// Forgets the last search, so its steps aren't counted for the next
+    fn reset(&mut self) {
+        self.stack.clear();
+        self.last_step = None;
+        self.conflicts.clear();
+
+        self.start = None;
+        self.started = Instant::now();
+        self.steps = 0;
+        self.backtracks = 0;
+        self.restarts = 0;
+        self.run_backtracks = 0;
+    }
+
+    pub fn last_step(&self) -> Option<&Step> {
+        self.last_step.as_ref()
+    }
+
+    /// Steps taken since `init`
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    pub fn backtracks(&self) -> u64 {
+        self.backtracks
+    }
+
+    pub fn gave_up(&self) -> bool {
+        self.last_step
+            .as_ref()
+            .is_some_and(|s| s.action == StepAction::GaveUp)
+    }
+
+    pub fn model(&self) -> &M {
+        &self.model
+    }
+
+    pub fn step(&mut self) -> StepResult<M::Wave> {
+        if self
+            .budget
+            .exceeded(self.steps, self.backtracks, self.started.elapsed())
+        {
+            self.last_step = Some(Step::without_cell(StepAction::GaveUp, self.stack.len()));
+            return StepResult::GaveUp;
         }
+        self.steps += 1;
 
-        posibilities.sort_by(|(_, e1), (_, e2)| e1.cmp(&e2));
-        let cell = posibilities.first().unwrap();
-        let els = b[cell.0].maybe_values();
+        let start = self.start.as_ref().unwrap();
+        let limit = self.restart.limit(self.restart_base, self.restarts);
+        if limit.is_some_and(|l| self.run_backtracks >= l) {
+            self.restarts += 1;
+            self.run_backtracks = 0;
+            self.stack = vec![start.clone()];
+            self.conflicts.clear();
+            self.last_step = Some(Step::without_cell(StepAction::Restart, 1));
+            return StepResult::InProgress(start.clone());
+        }
+
+        let w = match self.stack.last() {
+            Some(w) => w.clone(),
+            None => return StepResult::Impossible,
+        };
+        let uncollapsed = self.model.uncollapsed(&w);
+        if uncollapsed.is_empty() {
+            self.last_step = Some(Step::without_cell(StepAction::Complete, self.stack.len()));
+            return StepResult::Complete(w);
+        }
 
-        match els.choose(&mut thread_rng()) {
-            Some(&v) => {
-                let mut bb = b.clone();
+        let view = View {
+            model: &self.model,
+            wave: &w,
+        };
+        let cell = self.cells.select(&view, &uncollapsed);
+        let options = self.model.options(&w, cell).unwrap();
+        let els = values(options);
 
-                self.stack.last_mut().unwrap()[cell.0].maybe_unset(v);
+        let value = self.values.choose(&view, cell, &els);
+        match value {
+            Some(v) => {
+                let mut next = w.clone();
 
-                bb.collapse(cell.0, v);
-                self.stack.push(bb);
+                self.model.forbid(self.stack.last_mut().unwrap(), cell, v);
+
+                self.model.collapse(&mut next, cell, v);
+                self.stack.push(next);
+                self.conflicts.push(cell, v);
+            }
+            None if self.backjump => {
+                let start = values(self.model.options(start, cell).unwrap_or_default());
+                let model = &self.model;
+                let conflict = self
+                    .conflicts
+                    .blame(cell, &start, |d, c| model.rules_out(d, c));
+                match self.conflicts.jump(conflict) {
+                    Some(len) => self.stack.truncate(len),
+                    None => self.stack.clear(),
+                }
+                self.backtracks += 1;
+                self.run_backtracks += 1;
             }
             None => {
                 self.stack.pop();
+                self.conflicts.pop();
+                self.backtracks += 1;
+                self.run_backtracks += 1;
             }
         }
 
+        self.last_step = Some(Step {
+            cell: Some(cell),
+            entropy: options.len() as u16,
+            value,
+            remaining: els.into_iter().filter(|&e| Some(e) != value).collect(),
+            depth: self.stack.len(),
+            action: if value.is_some() {
+                StepAction::Push
+            } else {
+                StepAction::Pop
+            },
+        });
+
         match self.stack.last() {
-            Some(b) => StepResult::InProgress(*b),
+            Some(w) => StepResult::InProgress(w.clone()),
             None => StepResult::Impossible,
         }
     }
+
+    /// Throws away the complete board on top of the stack, so that the
+    /// following steps look for another solution
+    pub fn reject(&mut self) {
+        if self.backjump {
+            match self.conflicts.reject() {
+                Some(len) => self.stack.truncate(len),
+                None => self.stack.clear(),
+            }
+        } else {
+            self.stack.pop();
+            self.conflicts.pop();
+        }
+    }
+
+    /// Runs the search from `w` to the end, returning the first solution
+    /// found. See `gave_up` for whether it ran out of budget before that
+    pub fn run(&mut self, w: M::Wave) -> Option<M::Wave> {
+        self.init(w);
+        loop {
+            match self.step() {
+                StepResult::Complete(w) => return Some(w),
+                StepResult::InProgress(_) => (),
+                StepResult::Impossible | StepResult::GaveUp => return None,
+            }
+        }
+    }
+}
+
+impl Wfc {
+    /// Solves a puzzle as entered, with pencil marks narrowing it down
+    pub fn solve(&mut self, mut b: Board) -> Option<Board> {
+        if !b.can_solve() {
+            self.reset();
+            return None;
+        }
+
+        b.init_maybe();
+        self.run(b)
+    }
 }
@@ -0,0 +1,178 @@
+/// Set of decision levels, bit `i` for the decision that made board `i` on
+/// the stack. Grows with the stack, which can be hundreds of boards deep for
+/// models bigger than Sudoku
+#[derive(Clone, Default, PartialEq, Eq, Debug)]
+pub struct Levels(Vec<u64>);
+
+impl Levels {
+    fn single(level: usize) -> Self {
+        let mut l = Self::default();
+        l.insert(level);
+        l
+    }
+
+    /// Levels 1 to `n`
+    fn up_to(n: usize) -> Self {
+        let mut l = Self::default();
+        for level in 1..=n {
+            l.insert(level);
+        }
+        l
+    }
+
+    fn insert(&mut self, level: usize) {
+        let word = level / 64;
+        if self.0.len() <= word {
+            self.0.resize(word + 1, 0);
+        }
+        self.0[word] |= 1 << (level % 64);
+    }
+
+    fn remove(&mut self, level: usize) {
+        if let Some(w) = self.0.get_mut(level / 64) {
+            *w &= !(1 << (level % 64));
+        }
+    }
+
+    fn union(mut self, other: &Levels) -> Self {
+        if self.0.len() < other.0.len() {
+            self.0.resize(other.0.len(), 0);
+        }
+        for (w, o) in self.0.iter_mut().zip(&other.0) {
+            *w |= o;
+        }
+        self
+    }
+
+    /// The latest level, or `None` if there are none
+    fn highest(&self) -> Option<usize> {
+        let (word, w) = self.0.iter().enumerate().rev().find(|(_, &w)| w != 0)?;
+        Some(word * 64 + 63 - w.leading_zeros() as usize)
+    }
+}
+
+/// Remembers why candidates were ruled out, so a dead end can be traced
+/// back to the decisions that caused it
+#[derive(Default)]
+pub struct Conflicts {
+    /// Cell and value collapsed to make each board after the first
+    decisions: Vec<(usize, u8)>,
+    /// For each board, candidates ruled out on it because every attempt
+    /// with them failed, and the levels to blame for that
+    nogoods: Vec<Vec<(usize, u8, Levels)>>,
+}
+
+impl Conflicts {
+    pub fn clear(&mut self) {
+        self.decisions.clear();
+        self.nogoods = vec![vec![]];
+    }
+
+    pub fn push(&mut self, cell: usize, value: u8) {
+        self.decisions.push((cell, value));
+        self.nogoods.push(vec![]);
+    }
+
+    /// Forgets the latest decision after backtracking over it chronologically
+    pub fn pop(&mut self) {
+        self.decisions.pop();
+        self.nogoods.pop();
+    }
+
+    /// Levels to blame for `values` no longer being candidates of `cell`.
+    /// `rules_out` tells whether a decision's cell alone rules its value
+    /// out for another cell, as for `Model::rules_out`
+    pub fn blame(
+        &self,
+        cell: usize,
+        values: &[u8],
+        rules_out: impl Fn(usize, usize) -> bool,
+    ) -> Levels {
+        values.iter().fold(Levels::default(), |acc, &v| {
+            let decision = self
+                .decisions
+                .iter()
+                .position(|&(d, dv)| dv == v && rules_out(d, cell));
+            let nogood = self
+                .nogoods
+                .iter()
+                .flatten()
+                .find(|&&(c, cv, _)| c == cell && cv == v);
+
+            match (decision, nogood) {
+                (Some(i), _) => acc.union(&Levels::single(i + 1)),
+                (None, Some((_, _, levels))) => acc.union(levels),
+                // ruled out by propagation the model can't explain, so
+                // blame everything, which is always safe
+                (None, None) => acc.union(&Levels::up_to(self.decisions.len())),
+            }
+        })
+    }
+
+    /// Undoes the latest decision after it led to a solution. Every
+    /// decision before it is to blame, as they all led there too
+    pub fn reject(&mut self) -> Option<usize> {
+        self.jump(Levels::up_to(self.decisions.len()))
+    }
+
+    /// Undoes every decision after the latest one in `conflict` and records
+    /// that one as failed. Returns how many boards are left on the stack, or
+    /// `None` if nothing is to blame and there is no solution
+    pub fn jump(&mut self, mut conflict: Levels) -> Option<usize> {
+        let level = conflict.highest()?;
+        let (cell, value) = self.decisions[level - 1];
+        self.decisions.truncate(level - 1);
+        self.nogoods.truncate(level);
+        conflict.remove(level);
+        self.nogoods[level - 1].push((cell, value, conflict));
+        Some(level)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        board::{parse_collection, Board},
+        wfc::{parallel, Search},
+    };
+
+    /// Solutions to the hard examples with a stride of spaces emptied,
+    /// leaving each with from 3 to a few hundred solutions
+    fn puzzles() -> Vec<Board> {
+        let s = std::fs::read_to_string("example/hard.txt").unwrap();
+        let mut puzzles = vec![];
+        for (k, b) in parse_collection(&s).unwrap().into_iter().enumerate() {
+            let solution = Search::default().wfc().solve(b).unwrap().to_line();
+            for empty in [50, 55] {
+                let mut line = solution.clone().into_bytes();
+                for j in 0..empty {
+                    line[(j * 7 + k) % 81] = b'.';
+                }
+                let line = String::from_utf8(line).unwrap();
+                puzzles.extend(parse_collection(&line).unwrap());
+            }
+        }
+        puzzles.truncate(15);
+        puzzles
+    }
+
+    #[test]
+    fn counts_match_chronological() {
+        for (i, p) in puzzles().into_iter().enumerate() {
+            let counts = [(false, 1), (false, 4), (true, 1), (true, 4)].map(|(chrono, jobs)| {
+                let search = Search {
+                    chronological: chrono,
+                    ..Default::default()
+                };
+                parallel::count(p, &search, jobs)
+            });
+            assert!(counts[0].0 > 1, "puzzle {}", i);
+            assert!(
+                counts.iter().all(|&c| c == (counts[0].0, false)),
+                "puzzle {}: {:?}",
+                i,
+                counts
+            );
+        }
+    }
+}
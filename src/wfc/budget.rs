@@ -0,0 +1,70 @@
+use std::time::Duration;
+
+/// Backtracks before the first geometric restart grow by this each time
+const GEOMETRIC_FACTOR: f64 = 1.5;
+
+/// Limits on one search, any of which makes it give up
+#[derive(clap::Args, Clone, Copy, Default, Debug)]
+pub struct Budget {
+    /// Give up after this many solver steps
+    #[arg(long, global = true)]
+    pub max_steps: Option<u64>,
+
+    /// Give up after backtracking this many times
+    #[arg(long, global = true)]
+    pub max_backtracks: Option<u64>,
+
+    /// Give up after this many seconds, e.g. 0.5
+    #[arg(long, global = true, value_parser = parse_secs)]
+    pub time_limit: Option<Duration>,
+}
+
+impl Budget {
+    pub fn exceeded(&self, steps: u64, backtracks: u64, elapsed: Duration) -> bool {
+        self.max_steps.is_some_and(|m| steps >= m)
+            || self.max_backtracks.is_some_and(|m| backtracks >= m)
+            || self.time_limit.is_some_and(|m| elapsed >= m)
+    }
+}
+
+fn parse_secs(s: &str) -> Result<Duration, String> {
+    Duration::try_from_secs_f64(s.parse().map_err(|e| format!("{}", e))?).map_err(|e| e.to_string())
+}
+
+/// When to throw away the search so far and start again from the puzzle.
+/// Only useful with a random cell or value choice, which makes each run
+/// take a different path
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default, clap::ValueEnum)]
+pub enum Restart {
+    #[default]
+    Never,
+    /// After base times 1, 1, 2, 1, 1, 2, 4, 1, ... backtracks
+    Luby,
+    /// After base times 1, 1.5, 2.25, ... backtracks
+    Geometric,
+}
+
+impl Restart {
+    /// Backtracks allowed in the run following `restarts` restarts
+    pub fn limit(&self, base: u64, restarts: u32) -> Option<u64> {
+        match self {
+            Restart::Never => None,
+            Restart::Luby => Some(base * luby(restarts as u64 + 1)),
+            Restart::Geometric => {
+                Some((base as f64 * GEOMETRIC_FACTOR.powi(restarts as i32)) as u64)
+            }
+        }
+    }
+}
+
+/// The `i`th term of the Luby sequence, counting from 1
+fn luby(mut i: u64) -> u64 {
+    loop {
+        // smallest k with 2^k - 1 >= i
+        let k = 64 - i.leading_zeros();
+        if i == (1 << k) - 1 {
+            return 1 << (k - 1);
+        }
+        i -= (1 << (k - 1)) - 1;
+    }
+}
@@ -0,0 +1,113 @@
+use super::model::{values, Grid, Mask};
+use rand::{seq::SliceRandom, thread_rng, Rng};
+
+/// Picks which uncollapsed cell the search collapses next
+pub trait CellHeuristic: Send {
+    /// `cells` are the uncollapsed cells of `g` in index order with their
+    /// options, and never empty. Returns the index of the chosen one
+    fn select(&self, g: &dyn Grid, cells: &[(usize, Mask)]) -> usize;
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default, clap::ValueEnum)]
+pub enum CellChoice {
+    /// Fewest candidates, ties broken by index order
+    #[default]
+    Mrv,
+    /// Fewest candidates, ties broken by most uncollapsed peers
+    MrvDegree,
+    /// Fewest candidates, ties broken at random
+    MrvRandom,
+    /// Lowest Shannon entropy of the values by their weights, ties broken
+    /// at random
+    Entropy,
+    /// First uncollapsed cell in index order
+    FirstEmpty,
+}
+
+impl CellChoice {
+    pub fn heuristic(&self) -> Box<dyn CellHeuristic> {
+        match self {
+            CellChoice::Mrv => Box::new(Mrv),
+            CellChoice::MrvDegree => Box::new(MrvDegree),
+            CellChoice::MrvRandom => Box::new(MrvRandom),
+            CellChoice::Entropy => Box::new(WeightedEntropy),
+            CellChoice::FirstEmpty => Box::new(FirstEmpty),
+        }
+    }
+}
+
+pub struct Mrv;
+
+impl CellHeuristic for Mrv {
+    fn select(&self, _g: &dyn Grid, cells: &[(usize, Mask)]) -> usize {
+        cells.iter().min_by_key(|(_, o)| o.len()).unwrap().0
+    }
+}
+
+pub struct MrvDegree;
+
+impl CellHeuristic for MrvDegree {
+    fn select(&self, g: &dyn Grid, cells: &[(usize, Mask)]) -> usize {
+        let degree = |i: usize| {
+            g.neighbours(i)
+                .into_iter()
+                .filter(|&p| g.options(p).is_some())
+                .count()
+        };
+
+        // max_by_key keeps the last maximum, so reverse to prefer low indexes
+        cells
+            .iter()
+            .rev()
+            .max_by_key(|(i, o)| (std::cmp::Reverse(o.len()), degree(*i)))
+            .unwrap()
+            .0
+    }
+}
+
+pub struct MrvRandom;
+
+impl CellHeuristic for MrvRandom {
+    fn select(&self, _g: &dyn Grid, cells: &[(usize, Mask)]) -> usize {
+        let min = cells.iter().map(|(_, o)| o.len()).min().unwrap();
+        let ties = cells
+            .iter()
+            .filter(|(_, o)| o.len() == min)
+            .collect::<Vec<_>>();
+        ties.choose(&mut thread_rng()).unwrap().0
+    }
+}
+
+pub struct WeightedEntropy;
+
+impl CellHeuristic for WeightedEntropy {
+    fn select(&self, g: &dyn Grid, cells: &[(usize, Mask)]) -> usize {
+        let mut rng = thread_rng();
+        // -sum(p log p) with p = w / total, without dividing every weight
+        let entropy = |o: Mask| {
+            let weights = values(o).into_iter().map(|v| g.weight(v));
+            let (total, sum) = weights
+                .filter(|&w| w > 0.0)
+                .fold((0.0, 0.0), |(t, s), w| (t + w, s + w * w.ln()));
+            total.ln() - sum / total
+        };
+
+        cells
+            .iter()
+            // far smaller than the gap between any two real entropies
+            .map(|(i, o)| (*i, entropy(*o) + rng.gen::<f64>() * 1e-6))
+            // a cell without values has to be picked so the search backtracks
+            .map(|(i, e)| (i, if e.is_nan() { f64::NEG_INFINITY } else { e }))
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .unwrap()
+            .0
+    }
+}
+
+pub struct FirstEmpty;
+
+impl CellHeuristic for FirstEmpty {
+    fn select(&self, _g: &dyn Grid, cells: &[(usize, Mask)]) -> usize {
+        cells[0].0
+    }
+}
@@ -0,0 +1,251 @@
+use crate::board::{are_peers, peers, Board, Cell};
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign};
+
+/// A problem the search can solve: cells that each collapse to one of up to
+/// 256 values, and rules for how a value narrows down the other cells
+pub trait Model {
+    /// What the search knows about every cell, copied for each decision
+    type Wave: Clone;
+
+    fn cells(&self) -> usize;
+
+    /// Values cell `idx` may still take as bits, or `None` once it has one
+    fn options(&self, w: &Self::Wave, idx: usize) -> Option<Mask>;
+
+    fn value(&self, w: &Self::Wave, idx: usize) -> Option<u8>;
+
+    /// Cells without a value and their options, in index order
+    fn uncollapsed(&self, w: &Self::Wave) -> Vec<(usize, Mask)> {
+        (0..self.cells())
+            .filter_map(|i| self.options(w, i).map(|o| (i, o)))
+            .collect()
+    }
+
+    /// Cells whose options a value for `idx` narrows down directly
+    fn neighbours(&self, idx: usize) -> Vec<usize>;
+
+    /// Gives cell `idx` the value `v`, narrowing down the others to match
+    fn collapse(&self, w: &mut Self::Wave, idx: usize, v: u8);
+
+    /// Rules out `v` for cell `idx`
+    fn forbid(&self, w: &mut Self::Wave, idx: usize, v: u8);
+
+    /// How likely `v` is relative to the other values, for the weighted
+    /// entropy and the random value choice
+    fn weight(&self, _v: u8) -> f64 {
+        1.0
+    }
+
+    /// Whether a value for `decided` on its own rules out that same value
+    /// for `cell`. Lets backjumping skip decisions that had nothing to do
+    /// with a dead end. Without it every decision is blamed, which is slower
+    /// but always right
+    fn rules_out(&self, _decided: usize, _cell: usize) -> bool {
+        false
+    }
+}
+
+/// A model with one of its waves, as the cell and value choices see it
+pub trait Grid {
+    fn cells(&self) -> usize;
+    fn options(&self, idx: usize) -> Option<Mask>;
+    fn value(&self, idx: usize) -> Option<u8>;
+    fn neighbours(&self, idx: usize) -> Vec<usize>;
+    fn weight(&self, v: u8) -> f64;
+    /// How many options of the other cells a value for `idx` would rule out
+    fn ruled_out(&self, idx: usize, v: u8) -> usize;
+}
+
+pub struct View<'a, M: Model> {
+    pub model: &'a M,
+    pub wave: &'a M::Wave,
+}
+
+impl<M: Model> Grid for View<'_, M> {
+    fn cells(&self) -> usize {
+        self.model.cells()
+    }
+
+    fn options(&self, idx: usize) -> Option<Mask> {
+        self.model.options(self.wave, idx)
+    }
+
+    fn value(&self, idx: usize) -> Option<u8> {
+        self.model.value(self.wave, idx)
+    }
+
+    fn neighbours(&self, idx: usize) -> Vec<usize> {
+        self.model.neighbours(idx)
+    }
+
+    fn weight(&self, v: u8) -> f64 {
+        self.model.weight(v)
+    }
+
+    fn ruled_out(&self, idx: usize, v: u8) -> usize {
+        let mut after = self.wave.clone();
+        self.model.collapse(&mut after, idx, v);
+
+        let count = |w: &M::Wave, i: usize| self.model.options(w, i).map_or(0, |o| o.len());
+        (0..self.model.cells())
+            .filter(|&i| i != idx)
+            .map(|i| (count(self.wave, i) - count(&after, i)) as usize)
+            .sum()
+    }
+}
+
+/// The values set in a mask of options, smallest first
+pub fn values(options: Mask) -> Vec<u8> {
+    let mut values = vec![];
+    for (i, &word) in options.0.iter().enumerate() {
+        let mut word = word;
+        while word != 0 {
+            values.push((i * 64) as u8 + word.trailing_zeros() as u8);
+            word &= word - 1;
+        }
+    }
+    values
+}
+
+/// Set of values from 0 to 255 as bits, the options of a cell
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub struct Mask([u64; 4]);
+
+impl Mask {
+    /// The values below `n`
+    pub fn below(n: usize) -> Self {
+        let mut m = Self::default();
+        for (i, word) in m.0.iter_mut().enumerate() {
+            *word = match n.saturating_sub(i * 64) {
+                0 => 0,
+                k if k >= 64 => u64::MAX,
+                k => (1 << k) - 1,
+            };
+        }
+        m
+    }
+
+    pub fn single(v: u8) -> Self {
+        let mut m = Self::default();
+        m.insert(v);
+        m
+    }
+
+    pub fn contains(&self, v: u8) -> bool {
+        self.0[v as usize / 64] & 1 << (v % 64) != 0
+    }
+
+    pub fn insert(&mut self, v: u8) {
+        self.0[v as usize / 64] |= 1 << (v % 64);
+    }
+
+    pub fn remove(&mut self, v: u8) {
+        self.0[v as usize / 64] &= !(1 << (v % 64));
+    }
+
+    pub fn len(&self) -> u32 {
+        self.0.iter().map(|w| w.count_ones()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == [0; 4]
+    }
+}
+
+impl From<u64> for Mask {
+    fn from(bits: u64) -> Self {
+        Self([bits, 0, 0, 0])
+    }
+}
+
+impl BitAnd for Mask {
+    type Output = Self;
+
+    fn bitand(mut self, rhs: Self) -> Self {
+        self &= rhs;
+        self
+    }
+}
+
+impl BitAndAssign for Mask {
+    fn bitand_assign(&mut self, rhs: Self) {
+        for (a, b) in self.0.iter_mut().zip(rhs.0) {
+            *a &= b;
+        }
+    }
+}
+
+impl BitOr for Mask {
+    type Output = Self;
+
+    fn bitor(mut self, rhs: Self) -> Self {
+        self |= rhs;
+        self
+    }
+}
+
+impl BitOrAssign for Mask {
+    fn bitor_assign(&mut self, rhs: Self) {
+        for (a, b) in self.0.iter_mut().zip(rhs.0) {
+            *a |= b;
+        }
+    }
+}
+
+/// Sudoku on a `Board`, where a value rules itself out for the cell's peers
+#[derive(Clone, Copy)]
+pub struct Sudoku {
+    /// Weight of each digit from 1 to 9
+    weights: [f64; 9],
+}
+
+impl Default for Sudoku {
+    fn default() -> Self {
+        Self::weighted([1.0; 9])
+    }
+}
+
+impl Sudoku {
+    pub fn weighted(weights: [f64; 9]) -> Self {
+        Self { weights }
+    }
+}
+
+impl Model for Sudoku {
+    type Wave = Board;
+
+    fn cells(&self) -> usize {
+        81
+    }
+
+    fn options(&self, b: &Board, idx: usize) -> Option<Mask> {
+        match b[idx] {
+            Cell::Maybe(m) => Some(Mask::from(m as u64)),
+            _ => None,
+        }
+    }
+
+    fn value(&self, b: &Board, idx: usize) -> Option<u8> {
+        b[idx].value()
+    }
+
+    fn neighbours(&self, idx: usize) -> Vec<usize> {
+        peers(idx)
+    }
+
+    fn collapse(&self, b: &mut Board, idx: usize, v: u8) {
+        b.collapse(idx, v);
+    }
+
+    fn forbid(&self, b: &mut Board, idx: usize, v: u8) {
+        b[idx].maybe_unset(v);
+    }
+
+    fn weight(&self, v: u8) -> f64 {
+        self.weights[v as usize - 1]
+    }
+
+    fn rules_out(&self, decided: usize, cell: usize) -> bool {
+        are_peers(decided, cell)
+    }
+}
@@ -0,0 +1,219 @@
+use super::{
+    model::{Mask, Model},
+    tiled::{Dir, TileWave, Tiled},
+};
+use std::collections::HashMap;
+
+/// Which transformed copies of each pattern to add besides the original
+#[derive(Clone, Copy, Default, Debug)]
+pub struct Augment {
+    /// Quarter turns
+    pub rotate: bool,
+    /// Mirror images
+    pub reflect: bool,
+}
+
+/// The overlapping model: the output is covered by NxN patterns taken from a
+/// sample, each agreeing with its neighbours where they overlap, so it
+/// looks like the sample up close
+#[derive(Clone)]
+pub struct Overlapping {
+    n: usize,
+    width: usize,
+    height: usize,
+    /// Characters of each pattern row by row
+    patterns: Vec<Vec<char>>,
+    /// How often each pattern appears in the sample
+    counts: Vec<u32>,
+    /// One tile per pattern, placed at each pattern's top left corner
+    tiled: Tiled,
+}
+
+impl Overlapping {
+    /// Model for a `width` by `height` output from the patterns of
+    /// `sample`, a grid of characters with short lines padded by spaces
+    pub fn new(
+        sample: &str,
+        n: usize,
+        (width, height): (usize, usize),
+        augment: Augment,
+    ) -> Result<Self, String> {
+        let rows = sample.lines().collect::<Vec<_>>();
+        let sample_width = rows.iter().map(|r| r.chars().count()).max().unwrap_or(0);
+        if n == 0 || rows.len() < n || sample_width < n {
+            return Err(format!("The sample must be at least {0}x{0}", n));
+        }
+        if width < n || height < n {
+            return Err(format!("The output must be at least {0}x{0}", n));
+        }
+        let grid = rows
+            .iter()
+            .map(|r| {
+                let mut r = r.chars().collect::<Vec<_>>();
+                r.resize(sample_width, ' ');
+                r
+            })
+            .collect::<Vec<_>>();
+
+        let mut index = HashMap::new();
+        let mut patterns = vec![];
+        let mut counts = vec![];
+        for y in 0..=grid.len() - n {
+            for x in 0..=sample_width - n {
+                let p = (0..n * n).map(|i| grid[y + i / n][x + i % n]).collect();
+                for p in variants(p, n, augment) {
+                    let i = *index.entry(p.clone()).or_insert_with(|| {
+                        patterns.push(p);
+                        counts.push(0);
+                        patterns.len() - 1
+                    });
+                    counts[i] += 1;
+                }
+            }
+        }
+        if patterns.len() > 256 {
+            return Err(format!(
+                "The sample has {} different {}x{} patterns, at most 256 are supported",
+                patterns.len(),
+                n,
+                n
+            ));
+        }
+
+        let mut tiled = Tiled::new(width - n + 1, height - n + 1, patterns.len());
+        for (a, pa) in patterns.iter().enumerate() {
+            for (b, pb) in patterns.iter().enumerate() {
+                let at = |p: &[char], x: usize, y: usize| p[y * n + x];
+                let (a, b) = (a as u8, b as u8);
+                if (0..n).all(|y| (1..n).all(|x| at(pa, x, y) == at(pb, x - 1, y))) {
+                    tiled.allow(a, Dir::Right, b);
+                }
+                if (1..n).all(|y| (0..n).all(|x| at(pa, x, y) == at(pb, x, y - 1))) {
+                    tiled.allow(a, Dir::Down, b);
+                }
+            }
+        }
+
+        for (p, &c) in counts.iter().enumerate() {
+            tiled.weigh(p as u8, c as f64);
+        }
+
+        Ok(Self {
+            n,
+            width,
+            height,
+            patterns,
+            counts,
+            tiled,
+        })
+    }
+
+    pub fn wave(&self) -> TileWave {
+        self.tiled.wave()
+    }
+
+    pub fn counts(&self) -> &[u32] {
+        &self.counts
+    }
+
+    /// The output as lines of text, with `?` where no pattern was placed
+    pub fn render(&self, w: &TileWave) -> Vec<String> {
+        let (pw, ph) = (self.tiled.width(), self.tiled.height());
+        (0..self.height)
+            .map(|y| {
+                (0..self.width)
+                    .map(|x| {
+                        // the last patterns also cover the cells to their
+                        // right and below
+                        let (px, py) = (x.min(pw - 1), y.min(ph - 1));
+                        match w.tiles()[py * pw + px] {
+                            Some(p) => self.patterns[p as usize][(y - py) * self.n + x - px],
+                            None => '?',
+                        }
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+/// A pattern and its rotations and reflections, as asked for
+fn variants(p: Vec<char>, n: usize, augment: Augment) -> Vec<Vec<char>> {
+    let rotate = |p: &[char]| (0..n * n).map(|i| p[(n - 1 - i % n) * n + i / n]).collect();
+    let reflect = |p: &[char]| (0..n * n).map(|i| p[i / n * n + n - 1 - i % n]).collect();
+
+    let mut all = vec![p];
+    if augment.rotate {
+        for _ in 0..3 {
+            let next = rotate(all.last().unwrap());
+            all.push(next);
+        }
+    }
+    if augment.reflect {
+        let reflected = all.iter().map(|p| reflect(p)).collect::<Vec<_>>();
+        all.extend(reflected);
+    }
+    all
+}
+
+impl Model for Overlapping {
+    type Wave = TileWave;
+
+    fn cells(&self) -> usize {
+        self.tiled.cells()
+    }
+
+    fn options(&self, w: &TileWave, idx: usize) -> Option<Mask> {
+        self.tiled.options(w, idx)
+    }
+
+    fn value(&self, w: &TileWave, idx: usize) -> Option<u8> {
+        self.tiled.value(w, idx)
+    }
+
+    fn neighbours(&self, idx: usize) -> Vec<usize> {
+        self.tiled.neighbours(idx)
+    }
+
+    fn weight(&self, p: u8) -> f64 {
+        self.tiled.weight(p)
+    }
+
+    fn collapse(&self, w: &mut TileWave, idx: usize, v: u8) {
+        self.tiled.collapse(w, idx, v)
+    }
+
+    fn forbid(&self, w: &mut TileWave, idx: usize, v: u8) {
+        self.tiled.forbid(w, idx, v)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wfc::{CellChoice, Search};
+
+    #[test]
+    fn generates_from_island_turned_and_mirrored() {
+        let sample = std::fs::read_to_string("example/island.txt").unwrap();
+        let augment = Augment {
+            rotate: true,
+            reflect: true,
+        };
+        let model = Overlapping::new(&sample, 3, (30, 12), augment).unwrap();
+        // more patterns than fit in a u64 of options
+        assert!(model.counts().len() > 64);
+
+        let search = Search {
+            cells: Some(CellChoice::MrvRandom),
+            ..Default::default()
+        };
+        let wave = model.wave();
+        let mut wfc = search.wfc_for(model);
+        let map = wfc.run(wave).map(|w| wfc.model().render(&w)).unwrap();
+        assert_eq!(map.len(), 12);
+        assert!(map
+            .iter()
+            .all(|r| r.chars().count() == 30 && !r.contains('?')));
+    }
+}
@@ -0,0 +1,248 @@
+use super::{
+    model::{values, Model, View},
+    Budget, Restart, Search, StepResult, Wfc,
+};
+use crate::board::Board;
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Mutex,
+    },
+    thread,
+    time::Instant,
+};
+
+/// Subtrees to split a search into per thread, so a thread that finishes
+/// early has more to take on
+const SUBTREES_PER_JOB: usize = 8;
+
+/// Steps between checks on whether another thread is done or the budget
+/// has run out
+const CHECK_EVERY: u64 = 256;
+
+/// What the threads of one search have spent of its budget between them
+struct Spent {
+    steps: AtomicU64,
+    backtracks: AtomicU64,
+    started: Instant,
+}
+
+impl Spent {
+    fn new() -> Self {
+        Self {
+            steps: AtomicU64::new(0),
+            backtracks: AtomicU64::new(0),
+            started: Instant::now(),
+        }
+    }
+
+    /// Adds what `wfc` spent since `counted`, its steps and backtracks when
+    /// last charged, and returns whether `budget` is used up
+    fn charge<M: Model>(&self, budget: &Budget, wfc: &Wfc<M>, counted: &mut (u64, u64)) -> bool {
+        let (steps, backtracks) = (wfc.steps() - counted.0, wfc.backtracks() - counted.1);
+        *counted = (wfc.steps(), wfc.backtracks());
+        let steps = self.steps.fetch_add(steps, Ordering::Relaxed) + steps;
+        let backtracks = self.backtracks.fetch_add(backtracks, Ordering::Relaxed) + backtracks;
+        budget.exceeded(steps, backtracks, self.started.elapsed())
+    }
+}
+
+/// Solves `b` on `jobs` threads, each searching its own part of the tree.
+/// The first solution found wins. Also returns whether the search ran out
+/// of budget, which the threads share, in which case a missing solution may
+/// still exist
+pub fn solve(mut b: Board, search: &Search, jobs: usize) -> (Option<Board>, bool) {
+    if !b.can_solve() {
+        return (None, false);
+    }
+    b.init_maybe();
+    solve_model(search.sudoku(), b, search, jobs)
+}
+
+/// Counts the solutions of `b` on `jobs` threads. The threads share the
+/// search budget, and the count is only a lower bound if it ran out, which
+/// the second value tells. Restarts are never used, as they would count
+/// solutions again
+pub fn count(mut b: Board, search: &Search, jobs: usize) -> (u64, bool) {
+    if !b.can_solve() {
+        return (0, false);
+    }
+    b.init_maybe();
+    count_model(search.sudoku(), b, search, jobs)
+}
+
+/// `solve` for any model, from the wave `w`
+pub fn solve_model<M>(model: M, w: M::Wave, search: &Search, jobs: usize) -> (Option<M::Wave>, bool)
+where
+    M: Model + Clone + Send + Sync,
+    M::Wave: Send,
+{
+    let found = Mutex::new(None);
+    let stop = AtomicBool::new(false);
+    let gave_up = run(model, w, search, jobs, &stop, |solution| {
+        found.lock().unwrap().get_or_insert(solution);
+        stop.store(true, Ordering::Relaxed);
+        false
+    });
+    (found.into_inner().unwrap(), gave_up)
+}
+
+/// `count` for any model, from the wave `w`
+pub fn count_model<M>(model: M, w: M::Wave, search: &Search, jobs: usize) -> (u64, bool)
+where
+    M: Model + Clone + Send + Sync,
+    M::Wave: Send,
+{
+    let search = Search {
+        restart: Restart::Never,
+        ..*search
+    };
+    let solutions = AtomicU64::new(0);
+    let stop = AtomicBool::new(false);
+    let gave_up = run(model, w, &search, jobs, &stop, |_| {
+        solutions.fetch_add(1, Ordering::Relaxed);
+        true
+    });
+    (solutions.into_inner(), gave_up)
+}
+
+/// Splits the search from `w` and hands the parts to `jobs` threads,
+/// calling `found` with each solution until it returns false. Returns
+/// whether the budget ran out. It is checked every `CHECK_EVERY` steps of a
+/// thread, so the threads may go over it by that much each
+fn run<M>(
+    model: M,
+    w: M::Wave,
+    search: &Search,
+    jobs: usize,
+    stop: &AtomicBool,
+    found: impl Fn(M::Wave) -> bool + Sync,
+) -> bool
+where
+    M: Model + Clone + Send + Sync,
+    M::Wave: Send,
+{
+    let jobs = jobs.max(1);
+    let subtrees = Mutex::new(split(&model, w, search, jobs * SUBTREES_PER_JOB));
+    let gave_up = AtomicBool::new(false);
+    let spent = Spent::new();
+
+    thread::scope(|s| {
+        for _ in 0..jobs {
+            s.spawn(|| {
+                // the budget is for the whole search, not each subtree
+                let mut wfc = search.wfc_for(model.clone()).with_budget(Budget::default());
+                let out_of_budget = || {
+                    gave_up.store(true, Ordering::Relaxed);
+                    stop.store(true, Ordering::Relaxed);
+                };
+                loop {
+                    // the lock is only held while taking the next subtree
+                    let subtree = subtrees.lock().unwrap().pop_front();
+                    let subtree = match subtree {
+                        Some(w) if !stop.load(Ordering::Relaxed) => w,
+                        _ => break,
+                    };
+
+                    wfc.init(subtree);
+                    let mut counted = (0, 0);
+                    loop {
+                        if wfc.steps().is_multiple_of(CHECK_EVERY) {
+                            if spent.charge(&search.budget, &wfc, &mut counted) {
+                                return out_of_budget();
+                            }
+                            if stop.load(Ordering::Relaxed) {
+                                return;
+                            }
+                        }
+                        match wfc.step() {
+                            StepResult::Complete(solution) => {
+                                if !found(solution) {
+                                    return;
+                                }
+                                wfc.reject();
+                            }
+                            StepResult::InProgress(_) => (),
+                            StepResult::Impossible | StepResult::GaveUp => break,
+                        }
+                    }
+                    if spent.charge(&search.budget, &wfc, &mut counted) {
+                        return out_of_budget();
+                    }
+                }
+            });
+        }
+    });
+
+    gave_up.into_inner()
+}
+
+/// Expands the first decisions of the search breadth first, until there are
+/// at least `n` waves left to search or nothing left to expand. Between them
+/// the waves cover every solution of `w`. A cell's values are queued in the
+/// order the value choice would try them, so random choices stay random
+fn split<M: Model>(model: &M, w: M::Wave, search: &Search, n: usize) -> VecDeque<M::Wave> {
+    let cells = search.cells.unwrap_or_default().heuristic();
+    let order = search.values.order();
+    let mut waves = VecDeque::from([w]);
+    // complete waves can't be split further
+    let mut complete = vec![];
+
+    while waves.len() + complete.len() < n {
+        let w = match waves.pop_front() {
+            Some(w) => w,
+            None => break,
+        };
+
+        let uncollapsed = model.uncollapsed(&w);
+        if uncollapsed.is_empty() {
+            complete.push(w);
+            continue;
+        }
+
+        let view = View { model, wave: &w };
+        let cell = cells.select(&view, &uncollapsed);
+        let mut left = values(model.options(&w, cell).unwrap());
+        while let Some(v) = order.choose(&view, cell, &left) {
+            left.retain(|&l| l != v);
+            let mut child = w.clone();
+            model.collapse(&mut child, cell, v);
+            waves.push_back(child);
+        }
+    }
+
+    waves.extend(complete);
+    waves
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::parse_collection;
+    use std::time::Duration;
+
+    #[test]
+    fn shares_the_time_limit() {
+        // one given leaves far too many solutions to count in time
+        let line = "5".to_string() + &".".repeat(80);
+        let b = parse_collection(&line).unwrap()[0];
+        let search = Search {
+            budget: Budget {
+                time_limit: Some(Duration::from_millis(200)),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let start = Instant::now();
+        let (_, gave_up) = count(b, &search, 4);
+        assert!(gave_up);
+        // each of the subtrees taking the whole limit would take 1.6s
+        assert!(
+            start.elapsed() < Duration::from_secs(1),
+            "{:?}",
+            start.elapsed()
+        );
+    }
+}
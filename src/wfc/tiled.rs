@@ -0,0 +1,174 @@
+use super::model::{values, Mask, Model};
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Dir {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl Dir {
+    pub const ALL: [Dir; 4] = [Dir::Up, Dir::Down, Dir::Left, Dir::Right];
+
+    pub fn opposite(&self) -> Self {
+        match self {
+            Dir::Up => Dir::Down,
+            Dir::Down => Dir::Up,
+            Dir::Left => Dir::Right,
+            Dir::Right => Dir::Left,
+        }
+    }
+}
+
+/// A rectangle of tiles, where each tile only allows certain tiles next to
+/// it on each side. Tiles are numbered from 0 and there are at most 256
+#[derive(Clone)]
+pub struct Tiled {
+    width: usize,
+    height: usize,
+    tiles: usize,
+    /// Bit `b` of `allowed[dir][a]` is set if tile `b` may be `dir` of `a`
+    allowed: [Vec<Mask>; 4],
+    weights: Vec<f64>,
+}
+
+/// Options for each cell of a `Tiled`, row by row
+#[derive(Clone)]
+pub struct TileWave {
+    options: Vec<Mask>,
+    tiles: Vec<Option<u8>>,
+}
+
+impl TileWave {
+    /// Tile of each cell row by row, `None` for cells the search hasn't
+    /// collapsed
+    pub fn tiles(&self) -> &[Option<u8>] {
+        &self.tiles
+    }
+}
+
+impl Tiled {
+    /// Model where no tiles may be next to each other until allowed
+    pub fn new(width: usize, height: usize, tiles: usize) -> Self {
+        assert!(tiles <= 256, "at most 256 tiles");
+        Self {
+            width,
+            height,
+            tiles,
+            allowed: std::array::from_fn(|_| vec![Mask::default(); tiles]),
+            weights: vec![1.0; tiles],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Lets tile `b` be `dir` of tile `a`, and so `a` be opposite of `b`
+    pub fn allow(&mut self, a: u8, dir: Dir, b: u8) {
+        self.allowed[dir as usize][a as usize].insert(b);
+        self.allowed[dir.opposite() as usize][b as usize].insert(a);
+    }
+
+    /// Lets tiles `a` and `b` be next to each other on every side
+    pub fn allow_all(&mut self, a: u8, b: u8) {
+        for dir in Dir::ALL {
+            self.allow(a, dir, b);
+        }
+    }
+
+    /// Makes tile `t` `w` times as likely as a tile of weight 1
+    pub fn weigh(&mut self, t: u8, w: f64) {
+        self.weights[t as usize] = w;
+    }
+
+    /// Wave where every cell may still be any tile
+    pub fn wave(&self) -> TileWave {
+        TileWave {
+            options: vec![Mask::below(self.tiles); self.width * self.height],
+            tiles: vec![None; self.width * self.height],
+        }
+    }
+
+    fn step(&self, idx: usize, dir: Dir) -> Option<usize> {
+        let (x, y) = (idx % self.width, idx / self.width);
+        match dir {
+            Dir::Up if y > 0 => Some(idx - self.width),
+            Dir::Down if y + 1 < self.height => Some(idx + self.width),
+            Dir::Left if x > 0 => Some(idx - 1),
+            Dir::Right if x + 1 < self.width => Some(idx + 1),
+            _ => None,
+        }
+    }
+
+    /// Removes options that no option of a neighbour allows, starting from
+    /// the neighbours of `from`, until nothing changes
+    fn propagate(&self, w: &mut TileWave, from: usize) {
+        let mut queue = vec![from];
+        while let Some(idx) = queue.pop() {
+            for dir in Dir::ALL {
+                let n = match self.step(idx, dir) {
+                    Some(n) => n,
+                    None => continue,
+                };
+
+                let allowed = values(w.options[idx])
+                    .into_iter()
+                    .fold(Mask::default(), |acc, t| {
+                        acc | self.allowed[dir as usize][t as usize]
+                    });
+                let narrowed = w.options[n] & allowed;
+                if narrowed != w.options[n] {
+                    w.options[n] = narrowed;
+                    queue.push(n);
+                }
+            }
+        }
+    }
+}
+
+impl Model for Tiled {
+    type Wave = TileWave;
+
+    fn cells(&self) -> usize {
+        self.width * self.height
+    }
+
+    fn options(&self, w: &TileWave, idx: usize) -> Option<Mask> {
+        match w.tiles[idx] {
+            Some(_) => None,
+            None => Some(w.options[idx]),
+        }
+    }
+
+    fn value(&self, w: &TileWave, idx: usize) -> Option<u8> {
+        w.tiles[idx]
+    }
+
+    fn neighbours(&self, idx: usize) -> Vec<usize> {
+        Dir::ALL
+            .into_iter()
+            .filter_map(|d| self.step(idx, d))
+            .collect()
+    }
+
+    fn weight(&self, t: u8) -> f64 {
+        self.weights[t as usize]
+    }
+
+    fn collapse(&self, w: &mut TileWave, idx: usize, v: u8) {
+        w.tiles[idx] = Some(v);
+        w.options[idx] = Mask::single(v);
+        self.propagate(w, idx);
+    }
+
+    fn forbid(&self, w: &mut TileWave, idx: usize, v: u8) {
+        w.options[idx].remove(v);
+        self.propagate(w, idx);
+    }
+}
@@ -0,0 +1,74 @@
+use super::model::Grid;
+use rand::{seq::SliceRandom, thread_rng};
+
+/// Picks which value the search tries next for a cell
+pub trait ValueOrder: Send {
+    /// `values` are the untried values of cell `idx`, in ascending order.
+    /// Returns `None` only if there are none
+    fn choose(&self, g: &dyn Grid, idx: usize, values: &[u8]) -> Option<u8>;
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default, clap::ValueEnum)]
+pub enum ValueChoice {
+    /// Any value, at random in proportion to its weight
+    #[default]
+    Random,
+    /// Smallest value first
+    Ascending,
+    /// Least constraining value: the one ruling out the fewest candidates
+    /// from the cell's peers
+    Lcv,
+    /// The value already placed most often, as it has the fewest spaces left
+    Frequency,
+}
+
+impl ValueChoice {
+    pub fn order(&self) -> Box<dyn ValueOrder> {
+        match self {
+            ValueChoice::Random => Box::new(Random),
+            ValueChoice::Ascending => Box::new(Ascending),
+            ValueChoice::Lcv => Box::new(LeastConstraining),
+            ValueChoice::Frequency => Box::new(Frequency),
+        }
+    }
+}
+
+pub struct Random;
+
+impl ValueOrder for Random {
+    fn choose(&self, g: &dyn Grid, _idx: usize, values: &[u8]) -> Option<u8> {
+        let mut rng = thread_rng();
+        // every value left may weigh nothing, but one still has to be tried
+        match values.choose_weighted(&mut rng, |&v| g.weight(v)) {
+            Ok(&v) => Some(v),
+            Err(_) => values.choose(&mut rng).copied(),
+        }
+    }
+}
+
+pub struct Ascending;
+
+impl ValueOrder for Ascending {
+    fn choose(&self, _g: &dyn Grid, _idx: usize, values: &[u8]) -> Option<u8> {
+        values.first().copied()
+    }
+}
+
+pub struct LeastConstraining;
+
+impl ValueOrder for LeastConstraining {
+    fn choose(&self, g: &dyn Grid, idx: usize, values: &[u8]) -> Option<u8> {
+        // min_by_key keeps the first minimum, so ties go to the smaller value
+        values.iter().copied().min_by_key(|&v| g.ruled_out(idx, v))
+    }
+}
+
+pub struct Frequency;
+
+impl ValueOrder for Frequency {
+    fn choose(&self, g: &dyn Grid, _idx: usize, values: &[u8]) -> Option<u8> {
+        let placed = |v: u8| (0..g.cells()).filter(|&i| g.value(i) == Some(v)).count();
+        // rev so ties go to the smaller value
+        values.iter().rev().copied().max_by_key(|&v| placed(v))
+    }
+}